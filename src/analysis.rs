@@ -0,0 +1,367 @@
+use ratatui::widgets::TableState;
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use crate::json;
+use crate::model::{ArtifactKind, Data};
+use crate::preview::package_dirs;
+use crate::scanner::folder_stats;
+
+// Lockfile name, length and content hash.
+type LockfileKey = (&'static str, u64, u64);
+
+const LOCKFILES: [&str; 5] = [
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lock",
+];
+
+// Artifacts of projects with byte-identical lockfiles, which install the same tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockfileTwins {
+    pub lockfile: &'static str,
+    pub artifacts: Vec<(PathBuf, u64)>,
+}
+
+impl LockfileTwins {
+    // Everything beyond the largest copy, which would be kept and shared.
+    pub fn redundant(&self) -> u64 {
+        let total: u64 = self.artifacts.iter().map(|(_, bytes)| bytes).sum();
+        let largest = self.artifacts.iter().map(|(_, bytes)| *bytes).max();
+        total - largest.unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicatePackage {
+    pub name: String,
+    pub version: String,
+    pub copies: Vec<(PathBuf, u64)>,
+}
+
+impl DuplicatePackage {
+    pub fn total(&self) -> u64 {
+        self.copies.iter().map(|(_, bytes)| bytes).sum()
+    }
+
+    pub fn largest_copy(&self) -> u64 {
+        self.copies
+            .iter()
+            .map(|(_, bytes)| *bytes)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Everything beyond a single shared copy.
+    pub fn redundant(&self) -> u64 {
+        self.total() - self.largest_copy()
+    }
+}
+
+pub struct DuplicateView {
+    pub packages: Vec<DuplicatePackage>,
+    pub savings: Vec<(PathBuf, u64)>,
+    pub show_savings: bool,
+    pub state: TableState,
+}
+
+impl DuplicateView {
+    pub fn new(packages: Vec<DuplicatePackage>) -> Self {
+        Self {
+            savings: savings_by_project(&packages),
+            packages,
+            show_savings: false,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    pub fn toggle_savings(&mut self) {
+        self.show_savings = !self.show_savings;
+        self.state.select(Some(0));
+    }
+
+    fn len(&self) -> usize {
+        if self.show_savings {
+            self.savings.len()
+        } else {
+            self.packages.len()
+        }
+    }
+
+    pub fn redundant(&self) -> u64 {
+        self.packages.iter().map(DuplicatePackage::redundant).sum()
+    }
+
+    pub fn next_row(&mut self) {
+        if self.len() > 0 {
+            let i = self.state.selected().map_or(0, |i| (i + 1) % self.len());
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn previous_row(&mut self) {
+        if self.len() > 0 {
+            let count = self.len();
+            let i = self.state.selected().map_or(0, |i| (i + count - 1) % count);
+            self.state.select(Some(i));
+        }
+    }
+}
+
+pub fn find_duplicates(artifacts: &[PathBuf]) -> Vec<DuplicatePackage> {
+    let installed: Vec<(String, String, PathBuf, u64)> = artifacts
+        .par_iter()
+        .flat_map(|artifact| {
+            package_dirs(artifact)
+                .into_par_iter()
+                .filter_map(|(name, path)| {
+                    let (name, version) = match package_version(&path) {
+                        Some(version) => (name, version),
+                        None => pnpm_store_entry(&name)?,
+                    };
+                    let bytes = folder_stats(&path).ok()?.bytes;
+                    Some((name, version, artifact.clone(), bytes))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let mut grouped: HashMap<(String, String), Vec<(PathBuf, u64)>> = HashMap::new();
+    for (name, version, artifact, bytes) in installed {
+        grouped
+            .entry((name, version))
+            .or_default()
+            .push((artifact, bytes));
+    }
+
+    let mut duplicates: Vec<DuplicatePackage> = grouped
+        .into_iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .map(|((name, version), copies)| DuplicatePackage {
+            name,
+            version,
+            copies,
+        })
+        .collect();
+    duplicates.sort_by_key(|package| std::cmp::Reverse(package.redundant()));
+    duplicates
+}
+
+// A project's first lockfile in LOCKFILES order is the one compared. Only installs
+// count, one per project: its yarn cache or turbo cache shares the lockfile too but
+// isn't another copy of the same tree.
+pub fn identical_lockfiles(items: &[Data]) -> Vec<LockfileTwins> {
+    let mut installs: HashMap<&Path, (&Path, u64)> = HashMap::new();
+    for data in items {
+        if !matches!(data.kind, ArtifactKind::NodeModules | ArtifactKind::Pnpm) {
+            continue;
+        }
+        let install = installs
+            .entry(&data.project)
+            .or_insert((&data.path, data.bytes));
+        if data.bytes > install.1 {
+            *install = (&data.path, data.bytes);
+        }
+    }
+
+    let hashed: Vec<(LockfileKey, PathBuf, u64)> = installs
+        .into_par_iter()
+        .filter_map(|(project, (artifact, bytes))| {
+            let (lockfile, contents) = LOCKFILES
+                .iter()
+                .find_map(|name| Some((*name, fs::read(project.join(name)).ok()?)))?;
+            let mut hasher = DefaultHasher::new();
+            contents.hash(&mut hasher);
+            let key = (lockfile, contents.len() as u64, hasher.finish());
+            Some((key, artifact.to_path_buf(), bytes))
+        })
+        .collect();
+
+    let mut grouped: HashMap<LockfileKey, Vec<(PathBuf, u64)>> = HashMap::new();
+    for (key, artifact, bytes) in hashed {
+        grouped.entry(key).or_default().push((artifact, bytes));
+    }
+    let mut twins: Vec<LockfileTwins> = grouped
+        .into_iter()
+        .filter(|(_, artifacts)| artifacts.len() > 1)
+        .map(|((lockfile, ..), mut artifacts)| {
+            artifacts.sort();
+            LockfileTwins {
+                lockfile,
+                artifacts,
+            }
+        })
+        .collect();
+    twins.sort_by_key(|group| std::cmp::Reverse(group.redundant()));
+    twins
+}
+
+// With a content-addressable store every package version is stored once. The
+// largest copy is the one kept, and every other copy is saved at its own size, so
+// the projects add up to `redundant()`.
+pub fn savings_by_project(packages: &[DuplicatePackage]) -> Vec<(PathBuf, u64)> {
+    let mut savings: HashMap<PathBuf, u64> = HashMap::new();
+    for package in packages {
+        let kept = package
+            .copies
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, (_, bytes))| *bytes)
+            .map(|(i, _)| i);
+        for (i, (artifact, bytes)) in package.copies.iter().enumerate() {
+            let saved = if Some(i) == kept { 0 } else { *bytes };
+            *savings.entry(artifact.clone()).or_default() += saved;
+        }
+    }
+
+    let mut savings: Vec<(PathBuf, u64)> = savings.into_iter().collect();
+    savings.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    savings
+}
+
+fn package_version(package: &Path) -> Option<String> {
+    let manifest = fs::read_to_string(package.join("package.json")).ok()?;
+    json::parse(&manifest)?
+        .get("version")?
+        .as_str()
+        .map(str::to_string)
+}
+
+// pnpm virtual store entries are named like "@scope+name@1.2.3_peer" instead of
+// containing a package.json of their own.
+fn pnpm_store_entry(entry: &str) -> Option<(String, String)> {
+    let (at, _) = entry.char_indices().skip(1).find(|(_, c)| *c == '@')?;
+    let (name, version) = (&entry[..at], &entry[at + 1..]);
+    let version = version.split('_').next()?;
+    Some((name.replace('+', "/"), version.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DuplicatePackage, identical_lockfiles, pnpm_store_entry, savings_by_project};
+    use crate::model::{ArtifactKind, Data};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn savings_by_project_keeps_one_copy_in_total() {
+        let package = DuplicatePackage {
+            name: "react".to_string(),
+            version: "18.2.0".to_string(),
+            copies: vec![(PathBuf::from("/a"), 300), (PathBuf::from("/b"), 300)],
+        };
+        let savings = savings_by_project(std::slice::from_ref(&package));
+        let total: u64 = savings.iter().map(|(_, bytes)| bytes).sum();
+        assert_eq!(package.redundant(), total);
+        assert_eq!(2, savings.len());
+
+        // Copies of different sizes keep the largest; the rest count in full.
+        let packages = [
+            DuplicatePackage {
+                name: "lodash".to_string(),
+                version: "4.17.21".to_string(),
+                copies: vec![(PathBuf::from("/a"), 100), (PathBuf::from("/b"), 1000)],
+            },
+            DuplicatePackage {
+                name: "react".to_string(),
+                version: "18.2.0".to_string(),
+                copies: vec![
+                    (PathBuf::from("/a"), 100),
+                    (PathBuf::from("/b"), 100),
+                    (PathBuf::from("/c"), 100),
+                ],
+            },
+        ];
+        let savings: HashMap<PathBuf, u64> = savings_by_project(&packages).into_iter().collect();
+        let total: u64 = savings.values().sum();
+        assert_eq!(
+            packages
+                .iter()
+                .map(DuplicatePackage::redundant)
+                .sum::<u64>(),
+            total
+        );
+        assert_eq!(300, total);
+        assert_eq!(Some(&200), savings.get(Path::new("/a")));
+    }
+
+    #[test]
+    fn identical_lockfiles_groups_projects_with_the_same_lockfile() {
+        let root = std::env::temp_dir().join("nm-finder-rs-lockfiles");
+        let _ = fs::remove_dir_all(&root);
+        let mut rows: Vec<Data> = [("a", 300), ("b", 200), ("c", 100)]
+            .into_iter()
+            .map(|(name, bytes)| {
+                let project = root.join(name);
+                fs::create_dir_all(&project).unwrap();
+                Data {
+                    path: project.join("node_modules"),
+                    project,
+                    bytes,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        // Other artifacts of the same project share its lockfile but aren't twins.
+        for (path, kind) in [
+            ("a/.yarn/cache", ArtifactKind::YarnCache),
+            ("a/.turbo", ArtifactKind::TurboCache),
+        ] {
+            rows.push(Data {
+                path: root.join(path),
+                project: root.join("a"),
+                bytes: 50,
+                kind,
+                ..Default::default()
+            });
+        }
+        fs::write(root.join("a/yarn.lock"), "react@18.2.0").unwrap();
+        fs::write(root.join("b/yarn.lock"), "react@18.2.0").unwrap();
+        fs::write(root.join("c/yarn.lock"), "react@17.0.2").unwrap();
+
+        let twins = identical_lockfiles(&rows);
+        assert_eq!(1, twins.len());
+        assert_eq!("yarn.lock", twins[0].lockfile);
+        assert_eq!(
+            vec![rows[0].path.clone(), rows[1].path.clone()],
+            twins[0]
+                .artifacts
+                .iter()
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(200, twins[0].redundant());
+
+        // A project on its own has no twin, however many artifacts it has.
+        let project_a: Vec<Data> = rows
+            .iter()
+            .filter(|data| data.project == root.join("a"))
+            .cloned()
+            .collect();
+        assert!(identical_lockfiles(&project_a).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn pnpm_store_entry_splits_scoped_names_and_peer_suffixes() {
+        assert_eq!(
+            Some(("@babel/core".to_string(), "7.22.0".to_string())),
+            pnpm_store_entry("@babel+core@7.22.0_supports-color@9.0.0")
+        );
+        assert_eq!(
+            Some(("react".to_string(), "18.2.0".to_string())),
+            pnpm_store_entry("react@18.2.0")
+        );
+        assert_eq!(None, pnpm_store_entry("lock.yaml"));
+        assert_eq!(None, pnpm_store_entry(""));
+    }
+}
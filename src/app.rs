@@ -1,70 +1,358 @@
 use bytesize::ByteSize;
 use color_eyre::Result;
-use dir_size::get_size_in_bytes;
-use homedir::my_home;
 use ratatui::{
-    DefaultTerminal, Frame,
-    crossterm::event::{self, Event, KeyCode, KeyEventKind},
-    layout::{Constraint, Layout, Margin, Rect},
+    DefaultTerminal, Frame, Terminal,
+    backend::Backend,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Flex, Layout, Margin, Rect},
     style::{Modifier, Style, Stylize, palette::tailwind},
-    text::{Line, Text},
+    text::{Line, Span, Text},
     widgets::{
-        Block, BorderType, Cell, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation,
-        ScrollbarState, Table, TableState,
+        Block, BorderType, Cell, Clear, HighlightSpacing, List, ListItem, ListState, Paragraph,
+        Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState, Tabs,
     },
 };
 use rayon::prelude::*;
-use std::{fs::remove_dir_all, path::Path, str::FromStr};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    env,
+    fs::{self, symlink_metadata},
+    io,
+    path::{Path, PathBuf},
+    process,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, SystemTime},
+};
 use unicode_width::UnicodeWidthStr;
 
-use crate::model::{Data, TableColors};
-use crate::scanner::get_array;
-
-const PALETTES: [tailwind::Palette; 4] = [
-    tailwind::EMERALD,
-    tailwind::INDIGO,
-    tailwind::RED,
-    tailwind::BLUE,
-];
+use crate::analysis::{DuplicateView, LockfileTwins, find_duplicates, identical_lockfiles};
+use crate::config::{Config, Keymap};
+use crate::demo;
+use crate::drilldown::Drilldown;
+use crate::error::CleanerError;
+use crate::events;
+use crate::format::{
+    format_bytes, format_count, format_delta, format_share, format_size, size_bar, sparkline,
+    truncate_middle,
+};
+use crate::fuzzy::{fuzzy_positions, fuzzy_score, substring_positions};
+use crate::git;
+use crate::group::{GroupRow, GroupView, group_by_top_level};
+use crate::guard::{can_delete, outside_roots, running_as_root};
+use crate::hook::Hooks;
+use crate::in_use::find_in_use;
+use crate::message::{Effect, Message};
+use crate::model::{
+    ArtifactKind, ColumnWidths, Data, DeleteStatus, Heatmap, PALETTES, PathDisplay, SortField,
+    TableColors, display_path,
+};
+use crate::monorepo::collapse_nested;
+use crate::mounts::snapshot_mounts;
+use crate::palette::{self, Action, Criteria, filter_matches};
+use crate::preview::Preview;
+use crate::progress::Progress;
+use crate::project::{ProjectType, is_orphan};
+use crate::remove::{Deletion, DeletionEvent, Target, throughput};
+use crate::scanner::{
+    FolderStats, deletion_roots, folder_stats, get_array, home_owner, in_cloud_sync,
+    reclaimable_stats, scan_roots, size_pool, skipped_network_mounts,
+};
+use crate::space::{self, SpaceCheck};
+use crate::state::{
+    HISTORY_SAMPLES, History, Notes, Preset, Tags, View, load_bookmarks, load_history, load_notes,
+    load_presets, load_selection, load_tags, load_view, log_deletions, record_history,
+    save_bookmarks, save_history, save_notes, save_presets, save_selection, save_tags, save_view,
+    write_paths,
+};
+use crate::suggest::suggest;
+use crate::terminal;
 
 const ITEM_HEIGHT: usize = 4;
+const COMPACT_ITEM_HEIGHT: usize = 1;
+const PREVIEW_PERCENTAGE: u16 = 40;
+const TYPE_WIDTH: u16 = 12;
+const USER_WIDTH: u16 = 12;
+const TREND_WIDTH: u16 = HISTORY_SAMPLES as u16;
+const DELTA_WIDTH: u16 = 11;
+const BAR_WIDTH: u16 = 10;
+const SHARE_WIDTH: u16 = 6;
+const TOP_OFFENDERS: usize = 10;
+const NARROW_WIDTH: u16 = 100;
+const TABLE_HEADER_HEIGHT: u16 = 2;
+const TABLE_FOOTER_HEIGHT: u16 = 2;
+// Rows built either side of the visible window, in case a row's height differs.
+const RENDER_MARGIN: usize = 2;
+const DEMO_DELETE_PAUSE: Duration = Duration::from_millis(150);
+const DEMO_NOTICE: &str = " Demo mode: synthetic data, deletions are only simulated ";
+const TICK_RATE: Duration = Duration::from_millis(100);
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 pub struct App {
     state: TableState,
     items: Vec<Data>,
     longest_item_lens: (u16, u16, u16),
-    scroll_state: ScrollbarState,
     colors: TableColors,
     color_index: usize,
-    delete_folder: Vec<bool>,
-    sorted_by: u8,
+    row_height: usize,
+    sorted_by: SortField,
     sort_reversed: bool,
-    selected_size: ByteSize,
+    secondary_sort: Option<(SortField, bool)>,
+    sort_menu: Option<ListState>,
+    jump_query: Option<String>,
+    jump_origin: usize,
+    show_preview: bool,
+    previews: HashMap<PathBuf, Preview>,
+    narrow: bool,
+    deletion_warning: Option<Vec<(PathBuf, String)>>,
+    // The row `x` asked to delete; the selection is left alone while it's set.
+    quick_target: Option<PathBuf>,
+    confirmations_left: u8,
+    safety_window: Duration,
+    drilldown: Option<Drilldown>,
+    duplicates: Option<DuplicateView>,
+    groups: Option<GroupView>,
+    roots: Vec<PathBuf>,
+    root_menu: Option<ListState>,
+    // Only rows of this kind are listed; None is the "All" tab.
+    kind_tab: Option<ArtifactKind>,
+    // Roots added at runtime that are still being scanned.
+    root_scans: Vec<(PathBuf, Receiver<ScanResult>)>,
+    ticks: usize,
+    // Set whenever something on screen may have changed; the run loop skips drawing
+    // otherwise.
+    dirty: bool,
+    path_display: PathDisplay,
+    expanded_items: Option<Vec<Data>>,
+    hidden_items: Vec<Data>,
+    command_line: Option<String>,
+    command_error: Option<String>,
+    tags: Tags,
+    last_filter: String,
+    last_criteria: String,
+    notice: Option<String>,
+    deletion: Option<Deletion>,
+    space_check: Option<SpaceCheck>,
+    deleted_dirs: usize,
+    deleted_files: u64,
+    deleted_bytes: u64,
+    deleting_time: Duration,
+    read_only: bool,
+    keymap: Keymap,
+    all_users: bool,
+    // The scanned roots when running as root; nothing outside them may be deleted.
+    root_guard: Option<Vec<PathBuf>>,
+    hooks: Hooks,
+    history: History,
+    // Why `:suggest` picked a row.
+    suggestions: HashMap<PathBuf, String>,
+    // Sparklines drawn from `history`, which doesn't change once the app is up.
+    trends: HashMap<PathBuf, String>,
+    show_delta: bool,
+    bookmarks: BTreeSet<PathBuf>,
+    notes: Notes,
+    lockfile_twins: Vec<LockfileTwins>,
+    heatmap: Option<Heatmap>,
+    column_widths: ColumnWidths,
+    warn_size: Option<u64>,
+    critical_size: Option<u64>,
+    // Synthetic rows from `--demo`: deletions are simulated and nothing is saved.
+    demo: bool,
+    config: Config,
 }
 
 impl App {
-    pub fn new() -> Self {
-        let data_vec = generate_data();
-        let delete_files: Vec<bool> = vec![false; data_vec.len()];
-        let mut scroll_bar_length = 0;
-        if !data_vec.is_empty() {
-            scroll_bar_length = data_vec.len() - 1;
-        }
-        Self {
+    pub fn new(config: Config) -> Result<Self, CleanerError> {
+        let mut data_vec = generate_data(&config)?;
+        let mut history = load_history();
+        compare_with_history(&mut data_vec, &history);
+        let new_rows = data_vec
+            .iter()
+            .filter(|data| data.previous_bytes.is_none())
+            .count();
+        // On the very first run everything is new, which isn't worth pointing out.
+        let new_notice = (!history.is_empty() && new_rows > 0)
+            .then(|| format!(" {new_rows} new since the last scan, `:filter is:new` lists them "));
+        record_history(
+            &mut history,
+            data_vec.iter().map(|data| (data.path.clone(), data.bytes)),
+        );
+        let history_error = save_history(&history)
+            .err()
+            .map(|error| format!("could not save the size history: {error}"));
+        let notice = network_notice(&config).or(new_notice);
+        let mut app = Self::with_items(config, data_vec, history);
+        app.command_error = history_error;
+        app.notice = notice;
+        app.tags = load_tags();
+        app.bookmarks = load_bookmarks();
+        app.notes = load_notes();
+        app.restore_selection();
+        app.restore_view(load_view());
+        Ok(app)
+    }
+
+    // The sort and highlighted row the last session ended with. A sort set in the
+    // config or NMC_SORT still wins, otherwise it would stop working after one quit.
+    fn restore_view(&mut self, view: Option<View>) {
+        let Some(view) = view else {
+            return;
+        };
+        if !self.config.sort_configured {
+            self.sorted_by = view.sort_field;
+            self.sort_reversed = view.sort_reversed;
+            self.sort_items();
+        }
+        if let Some(i) = view
+            .highlighted
+            .and_then(|path| self.items.iter().position(|data| data.path == path))
+        {
+            self.select_row(i);
+        }
+    }
+
+    pub fn demo(config: Config) -> Self {
+        let items = demo::projects();
+        let history = demo::history(&items);
+        let config = Config {
+            root: Some(PathBuf::from(demo::ROOT)),
+            all_users: false,
+            all_mounts: false,
+            ..config
+        };
+        let mut app = Self::with_items(config, items, history);
+        app.demo = true;
+        app.notice = Some(DEMO_NOTICE.to_string());
+        app
+    }
+
+    // An app over already sized rows that reads nothing from the state directory,
+    // which is all the headless tests need.
+    pub fn with_items(config: Config, data_vec: Vec<Data>, history: History) -> Self {
+        let row_height = if config.compact_rows {
+            COMPACT_ITEM_HEIGHT
+        } else {
+            ITEM_HEIGHT
+        };
+        let color_index = config.palette % PALETTES.len();
+        let mut app = Self {
             state: TableState::default().with_selected(0),
             longest_item_lens: constraint_len_calculator(&data_vec),
-            scroll_state: ScrollbarState::new(scroll_bar_length * ITEM_HEIGHT),
-            colors: TableColors::new(&PALETTES[0]),
-            color_index: 0,
+            colors: PALETTES[color_index].1,
+            color_index,
+            row_height,
             items: data_vec,
-            delete_folder: delete_files,
-            sorted_by: 0,
-            sort_reversed: false,
-            selected_size: bytesize::ByteSize(0),
+            sorted_by: config.sort_field,
+            sort_reversed: config.sort_reversed,
+            secondary_sort: None,
+            sort_menu: None,
+            jump_query: None,
+            jump_origin: 0,
+            show_preview: false,
+            previews: HashMap::new(),
+            narrow: false,
+            deletion_warning: None,
+            quick_target: None,
+            confirmations_left: 0,
+            safety_window: config.safety_window(),
+            drilldown: None,
+            duplicates: None,
+            groups: None,
+            roots: scan_roots(&config),
+            root_menu: None,
+            kind_tab: None,
+            root_scans: Vec::new(),
+            ticks: 0,
+            dirty: true,
+            path_display: if config.absolute_paths {
+                PathDisplay::Absolute
+            } else {
+                PathDisplay::default()
+            },
+            expanded_items: None,
+            hidden_items: Vec::new(),
+            command_line: None,
+            command_error: None,
+            tags: Default::default(),
+            last_filter: String::new(),
+            last_criteria: String::new(),
+            notice: None,
+            deletion: None,
+            space_check: None,
+            deleted_dirs: 0,
+            deleted_files: 0,
+            deleted_bytes: 0,
+            deleting_time: Duration::ZERO,
+            read_only: config.read_only,
+            keymap: config.keymap,
+            all_users: config.all_users,
+            root_guard: running_as_root().then(|| deletion_roots(&scan_roots(&config), &config)),
+            hooks: config.hooks.clone(),
+            history,
+            trends: HashMap::new(),
+            suggestions: HashMap::new(),
+            show_delta: false,
+            bookmarks: Default::default(),
+            notes: Default::default(),
+            lockfile_twins: Vec::new(),
+            heatmap: config.heatmap,
+            column_widths: config.column_widths,
+            warn_size: config.warn_size,
+            critical_size: config.critical_size,
+            demo: false,
+            config,
+        };
+        app.lockfile_twins = identical_lockfiles(&app.items);
+        app.sort_items();
+        app
+    }
+
+    pub fn restore_selection(&mut self) {
+        let saved: HashSet<PathBuf> = load_selection().into_iter().collect();
+        if saved.is_empty() {
+            return;
         }
+        for data in &mut self.items {
+            if saved.contains(&data.path) {
+                data.selected = true;
+            }
+        }
+    }
+
+    // The selection plus the sort and highlighted row, restored on the next launch.
+    pub fn save_session(&self) -> Result<()> {
+        if self.demo {
+            return Ok(());
+        }
+        self.save_selection()?;
+        save_view(&View {
+            sort_field: self.sorted_by,
+            sort_reversed: self.sort_reversed,
+            highlighted: self
+                .state
+                .selected()
+                .and_then(|i| self.items.get(i))
+                .map(|data| data.path.clone()),
+        })?;
+        Ok(())
+    }
+
+    pub fn save_selection(&self) -> Result<()> {
+        let selected: Vec<PathBuf> = self
+            .items
+            .iter()
+            .filter(|data| data.selected)
+            .flat_map(|data| std::iter::once(&data.path).chain(&data.nested))
+            .cloned()
+            .collect();
+        save_selection(&selected)?;
+        Ok(())
     }
 
     pub fn next_row(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -75,11 +363,13 @@ impl App {
             }
             None => 0,
         };
-        self.state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+        self.select_row(i);
     }
 
     pub fn previous_row(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -90,299 +380,2995 @@ impl App {
             }
             None => 0,
         };
+        self.select_row(i);
+    }
+
+    pub fn select_row(&mut self, i: usize) {
         self.state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+    }
+
+    pub fn open_jump_prompt(&mut self) {
+        self.jump_origin = self.state.selected().unwrap_or_default();
+        self.jump_query = Some(String::new());
+    }
+
+    pub fn jump_to_best_match(&mut self) {
+        let Some(query) = self.jump_query.as_deref() else {
+            return;
+        };
+        let best = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, data)| {
+                fuzzy_score(query, &data.display_name()).map(|score| (score, i))
+            })
+            .max_by_key(|(score, i)| (*score, std::cmp::Reverse(*i)));
+        match best {
+            Some((_, i)) => self.select_row(i),
+            None => self.select_row(self.jump_origin),
+        }
+    }
+
+    // The first press goes to the largest unselected row, the next ones step down
+    // through the largest few and wrap around.
+    pub fn jump_to_largest(&mut self) {
+        let mut ranked: Vec<usize> = (0..self.items.len())
+            .filter(|&i| !self.items[i].selected && self.items[i].status.is_none())
+            .collect();
+        ranked.sort_by_key(|&i| std::cmp::Reverse(self.items[i].bytes));
+        ranked.truncate(TOP_OFFENDERS);
+        let current = self.state.selected();
+        let next = match ranked.iter().position(|&i| Some(i) == current) {
+            Some(rank) => ranked[(rank + 1) % ranked.len()],
+            None => match ranked.first() {
+                Some(&i) => i,
+                None => return,
+            },
+        };
+        self.select_row(next);
+    }
+
+    pub fn cancel_jump(&mut self) {
+        self.jump_query = None;
+        self.select_row(self.jump_origin);
     }
 
     pub fn select_for_deletion(&mut self) {
-        let i = self.state.selected().unwrap_or_default();
-        let abc = &ByteSize::from_str(&self.items[i].size).unwrap();
+        if let Some(data) = self.state.selected().and_then(|i| self.items.get_mut(i)) {
+            data.selected = !data.selected;
+        }
+    }
 
-        if self.delete_folder[i] {
-            self.delete_folder[i] = false;
-            self.items[i].selected_for_deletion = String::from("  ☐");
-            self.selected_size -= *abc;
-        } else {
-            self.delete_folder[i] = true;
-            self.items[i].selected_for_deletion = String::from("  ☑");
-            self.selected_size += *abc;
+    // npkill deletes the highlighted row straight away instead of building up a
+    // selection, so anything else selected is dropped first.
+    pub fn delete_highlighted(&mut self) {
+        let Some(current) = self.state.selected().filter(|i| *i < self.items.len()) else {
+            return;
+        };
+        for (i, data) in self.items.iter_mut().enumerate() {
+            data.selected = i == current;
+        }
+        self.request_deletion();
+    }
+
+    fn show_path(&self, path: &Path) -> String {
+        display_path(path, self.path_display == PathDisplay::Absolute)
+    }
+
+    pub fn is_recent(&self, data: &Data) -> bool {
+        data.is_recent(self.safety_window)
+    }
+
+    // Recent and locked rows are left out; they can still be picked one by one.
+    pub fn toggle_select_all(&mut self) {
+        let window = self.safety_window;
+        let selectable = |data: &Data| !data.is_recent(window) && !data.locked;
+        let select = self
+            .items
+            .iter()
+            .any(|data| !data.selected && selectable(data));
+        for data in &mut self.items {
+            if !select {
+                data.selected = false;
+            } else if selectable(data) {
+                data.selected = true;
+            }
         }
     }
 
     pub fn next_color(&mut self) {
         self.color_index = (self.color_index + 1) % PALETTES.len();
+        self.notice = Some(format!(" Palette: {} ", PALETTES[self.color_index].0));
     }
 
     pub fn previous_color(&mut self) {
         let count = PALETTES.len();
         self.color_index = (self.color_index + count - 1) % count;
+        self.notice = Some(format!(" Palette: {} ", PALETTES[self.color_index].0));
     }
 
     pub fn set_colors(&mut self) {
-        self.colors = TableColors::new(&PALETTES[self.color_index]);
+        self.colors = PALETTES[self.color_index].1;
     }
 
-    pub fn sort_by_next_field(&mut self) {
-        self.sort_reversed = false;
-        match self.sorted_by {
-            0 => {
-                self.items.sort_by_key(|data| data.name.clone());
-                self.sorted_by = 1;
-            }
-            1 => {
-                self.items
-                    .sort_by_key(|data| data.selected_for_deletion.clone());
-                self.sorted_by = 2;
-            }
-            _ => {
-                self.items.sort_by_key(|data| data.size.clone());
-                self.sorted_by = 0;
+    pub fn sort_items(&mut self) {
+        let (primary, reversed) = (self.sorted_by, self.sort_reversed);
+        let secondary = self.secondary_sort;
+        self.items.sort_by(|a, b| {
+            let ordering = directed(primary.compare(a, b), reversed);
+            match secondary {
+                Some((field, reversed)) => {
+                    ordering.then_with(|| directed(field.compare(a, b), reversed))
+                }
+                None => ordering,
             }
-        }
+        });
     }
 
-    pub fn remove_directories(&mut self) {
-        let homedir_binding = my_home().unwrap().unwrap();
-        let homedir = homedir_binding.to_str().unwrap();
-        let items_to_remove: Vec<String> = self
-            .items
-            .clone()
-            .into_par_iter()
-            .filter_map(|i| {
-                if i.selected_for_deletion == "  ☑" {
-                    let file_path = format!("{}{}", homedir, i.name);
-                    let _ = remove_dir_all(Path::new(&file_path));
-                    Some(file_path)
-                } else {
-                    None
-                }
-            })
-            .collect();
+    pub fn open_sort_menu(&mut self) {
+        let current = SortField::ALL
+            .iter()
+            .position(|field| *field == self.sorted_by);
+        self.sort_menu = Some(ListState::default().with_selected(current));
+    }
 
-        self.items
-            .retain(|data| !items_to_remove.contains(&format!("{}{}", homedir, data.name)));
+    pub fn next_sort_option(&mut self) {
+        if let Some(menu) = self.sort_menu.as_mut() {
+            let i = menu
+                .selected()
+                .map_or(0, |i| (i + 1) % SortField::ALL.len());
+            menu.select(Some(i));
+        }
     }
 
-    pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
-        loop {
-            terminal.draw(|frame| self.draw(frame))?;
+    pub fn previous_sort_option(&mut self) {
+        if let Some(menu) = self.sort_menu.as_mut() {
+            let count = SortField::ALL.len();
+            let i = menu.selected().map_or(0, |i| (i + count - 1) % count);
+            menu.select(Some(i));
+        }
+    }
 
-            if let Event::Key(key) = event::read()?
-                && key.kind == KeyEventKind::Press
+    pub fn apply_sort_option(&mut self, reversed: bool) {
+        if let Some(menu) = self.sort_menu.take() {
+            self.sorted_by = SortField::ALL[menu.selected().unwrap_or_default()];
+            self.sort_reversed = reversed;
+            if self
+                .secondary_sort
+                .is_some_and(|(field, _)| field == self.sorted_by)
             {
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
-                    KeyCode::Char('j') | KeyCode::Down => self.next_row(),
-                    KeyCode::Char('k') | KeyCode::Up => self.previous_row(),
-                    KeyCode::Char('l') | KeyCode::Right => self.next_color(),
-                    KeyCode::Char('h') | KeyCode::Left => {
-                        self.previous_color();
-                    }
-                    KeyCode::Enter => self.select_for_deletion(),
-                    KeyCode::Char('d') => self.remove_directories(),
-                    KeyCode::Char('r') => {
-                        self.items.reverse();
-                        self.sort_reversed = !self.sort_reversed;
-                    }
-                    KeyCode::Tab => self.sort_by_next_field(),
-                    _ => {}
-                }
+                self.secondary_sort = None;
             }
+            self.sort_items();
         }
     }
 
-    fn draw(&mut self, frame: &mut Frame) {
-        let vertical = &Layout::vertical([Constraint::Min(5), Constraint::Length(4)]);
-        let rects = vertical.split(frame.area());
+    pub fn apply_secondary_sort_option(&mut self, reversed: bool) {
+        if let Some(menu) = self.sort_menu.as_ref() {
+            let field = SortField::ALL[menu.selected().unwrap_or_default()];
+            self.secondary_sort = (field != self.sorted_by).then_some((field, reversed));
+            self.sort_items();
+        }
+    }
 
-        self.set_colors();
+    pub fn clear_secondary_sort(&mut self) {
+        self.secondary_sort = None;
+        self.sort_items();
+    }
 
-        self.render_table(frame, rects[0]);
-        self.render_scrollbar(frame, rects[0]);
-        self.render_footer(frame, rects[1]);
+    pub fn open_drilldown(&mut self) {
+        if let Some(data) = self.state.selected().and_then(|i| self.items.get(i)) {
+            self.drilldown = Some(Drilldown::load(data.display_name(), &data.path));
+        }
     }
 
-    fn render_table(&mut self, frame: &mut Frame, area: Rect) {
-        let header_style = Style::default()
-            .fg(self.colors.header_fg)
-            .bg(self.colors.header_bg);
-        let selected_row_style = Style::default()
-            .add_modifier(Modifier::REVERSED)
-            .fg(self.colors.selected_row_style_fg);
-        let selected_col_style = Style::default().fg(self.colors.selected_column_style_fg);
-        let selected_cell_style = Style::default()
-            .add_modifier(Modifier::REVERSED)
-            .fg(self.colors.selected_cell_style_fg);
+    pub fn open_duplicates(&mut self) {
+        let artifacts: Vec<PathBuf> = self
+            .items
+            .iter()
+            .filter(|data| matches!(data.kind, ArtifactKind::NodeModules | ArtifactKind::Pnpm))
+            .map(|data| data.path.clone())
+            .collect();
+        self.duplicates = Some(DuplicateView::new(find_duplicates(&artifacts)));
+    }
 
-        let sort_arrow = if self.sort_reversed { "↓" } else { "↑" };
-        let mut selected_header = "Selected".to_string();
-        if self.sorted_by == 2 {
-            selected_header.push(' ');
-            selected_header.push_str(sort_arrow);
+    pub fn open_groups(&mut self) {
+        self.groups = Some(GroupView::new(group_by_top_level(&self.items, &self.roots)));
+    }
+
+    // Leaves the group view on the highlighted member's row in the main list.
+    pub fn show_group_member(&mut self) {
+        let Some(path) = self.groups.as_ref().and_then(GroupView::selected_member) else {
+            return;
+        };
+        if let Some(i) = self.items.iter().position(|data| data.path == path) {
+            self.select_row(i);
         }
-        if self.selected_size != bytesize::ByteSize(0) {
-            selected_header.push_str(&format!("\n{}", self.selected_size));
+        self.groups = None;
+    }
+
+    pub fn request_deletion(&mut self) {
+        if self.read_only {
+            self.notice = Some(String::from(" Read-only mode: nothing can be deleted "));
+            return;
         }
-        let mut name_header = "Name".to_string();
-        if self.sorted_by == 1 {
-            name_header.push(' ');
-            name_header.push_str(sort_arrow);
+        let targets: Vec<(PathBuf, PathBuf)> = self
+            .deletion_candidates()
+            .into_iter()
+            .flat_map(|data| {
+                std::iter::once(&data.path)
+                    .chain(&data.nested)
+                    .map(|path| (path.clone(), data.project.clone()))
+            })
+            .collect();
+        if let Some(roots) = self.root_guard.as_ref() {
+            let outside = outside_roots(targets.iter().map(|(path, _)| path.as_path()), roots);
+            if let Some(path) = outside.first() {
+                self.notice = Some(format!(
+                    " Refusing to delete {} outside the scanned roots while running as root ",
+                    path.display()
+                ));
+                return;
+            }
         }
-        let mut size_header = "Size".to_string();
-        if self.sorted_by == 0 {
-            size_header.push(' ');
-            size_header.push_str(sort_arrow);
+        let mut warnings = find_in_use(&targets);
+        // Notes are often the only hint that someone else still needs a directory.
+        warnings.extend(targets.iter().filter_map(|(path, _)| {
+            Some((path.clone(), format!("note: {}", self.notes.get(path)?)))
+        }));
+        // An imported plan can select rows the filter hides; those are left alone.
+        if self.quick_target.is_none() {
+            warnings.extend(
+                self.hidden_items
+                    .iter()
+                    .filter(|data| data.selected)
+                    .map(|data| {
+                        let reason = "selected but hidden by the filter, so it is kept";
+                        (data.path.clone(), reason.to_string())
+                    }),
+            );
+        }
+        for (mount, fs_type) in snapshot_mounts(targets.iter().map(|(path, _)| path.as_path())) {
+            warnings.push((
+                mount,
+                format!("{fs_type} snapshots keep deleted files until they are removed"),
+            ));
+        }
+        // Running as root always asks, and asks twice.
+        if self.root_guard.is_some() {
+            warnings.push((
+                PathBuf::from("/"),
+                "running as root, press Y twice to delete".to_string(),
+            ));
+            self.confirmations_left = 2;
+        } else {
+            self.confirmations_left = 1;
+        }
+        if warnings.is_empty() && self.quick_target.is_none() {
+            self.remove_directories();
+        } else {
+            self.deletion_warning = Some(warnings);
         }
-        let header = [selected_header, name_header, size_header]
-            .into_iter()
-            .map(Cell::from)
-            .collect::<Row>()
-            .style(header_style)
-            .height(2);
-        let rows = self.items.iter().enumerate().map(|(i, data)| {
-            let color = match i % 2 {
-                0 => self.colors.normal_row_color,
-                _ => self.colors.alt_row_color,
-            };
-            let item = data.ref_array();
-            item.into_iter()
-                .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                .collect::<Row>()
-                .style(Style::new().fg(self.colors.row_fg).bg(color))
-                .height(4)
-        });
-        let bar = "";
-        let t = Table::new(
-            rows,
-            [
-                Constraint::Length(10),
-                Constraint::Min(self.longest_item_lens.1 + 1),
-                Constraint::Min(self.longest_item_lens.2 + 1),
-            ],
-        )
-        .header(header)
-        .row_highlight_style(selected_row_style)
-        .column_highlight_style(selected_col_style)
-        .cell_highlight_style(selected_cell_style)
-        .highlight_symbol(Text::from(vec![
-            "".into(),
-            bar.into(),
-            bar.into(),
-            "".into(),
-        ]))
-        .bg(self.colors.buffer_bg)
-        .highlight_spacing(HighlightSpacing::Always);
-        frame.render_stateful_widget(t, area, &mut self.state);
     }
 
-    fn render_scrollbar(&mut self, frame: &mut Frame, area: Rect) {
-        frame.render_stateful_widget(
-            Scrollbar::default()
-                .orientation(ScrollbarOrientation::VerticalRight)
-                .begin_symbol(None)
-                .end_symbol(None),
-            area.inner(Margin {
-                vertical: 1,
-                horizontal: 1,
-            }),
-            &mut self.scroll_state,
-        );
+    // `x`: just the highlighted row, always behind a confirmation, whatever else
+    // is selected.
+    pub fn quick_delete(&mut self) {
+        if self.busy_deleting() {
+            return;
+        }
+        let Some(data) = self.state.selected().and_then(|i| self.items.get(i)) else {
+            return;
+        };
+        if data.status == Some(DeleteStatus::Done) {
+            return;
+        }
+        self.quick_target = Some(data.path.clone());
+        self.request_deletion();
+        // Refused before asking, e.g. in read-only mode.
+        if self.deletion_warning.is_none() {
+            self.quick_target = None;
+        }
     }
 
-    fn render_footer(&self, frame: &mut Frame, area: Rect) {
-        let info_text: Vec<String> = vec![
-            "(Esc) quit | (↑) move up | (↓) move down | (→) next color | (←) previous color"
-                .to_string(),
-            "(Enter) select/deselect | (D) delete selected | (Tab) Sort by next field ↑ | (R) Reverse order ↓"
-                .to_string(),
-        ];
+    // Deletion events are matched against `self.items`, so nothing may move rows
+    // out of it (nesting, filters, tabs, presets) until the batch is done.
+    fn busy_deleting(&mut self) -> bool {
+        if self.deletion.is_some() {
+            self.notice = Some(" Wait for the deletion to finish ".to_string());
+        }
+        self.deletion.is_some()
+    }
 
-        let lines = info_text.into_iter().map(Line::from);
-        let info_footer = Paragraph::new(Text::from_iter(lines))
-            .style(
-                Style::new()
-                    .fg(self.colors.row_fg)
-                    .bg(self.colors.buffer_bg),
+    fn deletion_candidates(&self) -> Vec<&Data> {
+        match &self.quick_target {
+            Some(path) => self
+                .items
+                .iter()
+                .filter(|data| data.path == *path)
+                .collect(),
+            None => self.items.iter().filter(|data| data.selected).collect(),
+        }
+    }
+
+    pub fn remove_directories(&mut self) {
+        if self.deletion.is_some() || self.read_only {
+            self.quick_target = None;
+            return;
+        }
+        self.prune_deleted();
+        let selected = self.deletion_candidates();
+        if selected.is_empty() {
+            self.quick_target = None;
+            return;
+        }
+        let queued: HashSet<PathBuf> = selected.iter().map(|data| data.path.clone()).collect();
+        let space_check = (!self.demo)
+            .then(|| SpaceCheck::before(selected.iter().filter_map(|data| data.path.parent())));
+        let targets = selected
+            .iter()
+            .map(|data| Target {
+                path: data.path.clone(),
+                nested: data.nested.clone(),
+                bytes: data.bytes,
+                files: data.file_count,
+            })
+            .collect();
+        self.space_check = space_check;
+        self.deletion = Some(if self.demo {
+            Deletion::simulate(targets, DEMO_DELETE_PAUSE)
+        } else {
+            Deletion::start(
+                targets,
+                self.hooks.clone(),
+                deletion_roots(&self.roots, &self.config),
             )
-            .centered()
-            .block(
-                Block::bordered()
-                    .border_type(BorderType::Double)
-                    .border_style(Style::new().fg(self.colors.footer_border_color)),
-            );
+        });
+        // Failures from an earlier batch are cleared; still-selected rows are retried.
+        for data in &mut self.items {
+            data.status = queued.contains(&data.path).then_some(DeleteStatus::Queued);
+        }
+        self.quick_target = None;
+    }
 
-        frame.render_widget(info_footer, area)
+    // Rows deleted by an earlier batch stay visible (struck through) until the
+    // next batch or view change.
+    fn prune_deleted(&mut self) {
+        self.items
+            .retain(|data| data.status != Some(DeleteStatus::Done));
+        let last = self.items.len().saturating_sub(1);
+        if self.state.selected().is_some_and(|i| i > last) {
+            self.select_row(last);
+        }
     }
-}
 
-fn generate_data() -> Vec<Data> {
-    let homedir = my_home().unwrap().unwrap();
-    get_array()
-        .into_par_iter()
-        .filter_map(|i| {
-            let name = i.clone();
-            let file_path = format!("{}{}", homedir.to_str().unwrap(), i);
-            let parent = get_size_in_bytes(Path::new(&file_path)).expect("REASON");
-
-            let folder_size = ByteSize::b(parent);
-            Some(Data {
-                name,
-                size: folder_size.to_string(),
-                selected_for_deletion: String::from("  ☐"),
-            })
+    pub fn poll_deletion(&mut self) {
+        let Some(deletion) = self.deletion.as_mut() else {
+            return;
+        };
+        for event in deletion.poll() {
+            let (path, status) = match event {
+                DeletionEvent::Started(path) => (path, DeleteStatus::Deleting),
+                DeletionEvent::Finished(path, Ok(_)) => (path, DeleteStatus::Done),
+                DeletionEvent::Finished(path, Err(error)) => {
+                    (path, DeleteStatus::Failed(error.to_string()))
+                }
+            };
+            if let Some(i) = self.items.iter().position(|data| data.path == path) {
+                if status == DeleteStatus::Done {
+                    self.items[i].selected = false;
+                }
+                self.items[i].status = Some(status);
+            }
+        }
+        if !deletion.is_finished() {
+            return;
+        }
+        let Some(deletion) = self.deletion.take() else {
+            return;
+        };
+        let status = throughput(deletion.files, deletion.bytes, deletion.elapsed());
+        self.deleted_dirs += deletion.finished - deletion.failed;
+        self.deleted_files += deletion.files;
+        self.deleted_bytes += deletion.bytes;
+        self.deleting_time += deletion.elapsed();
+
+        let done: Vec<&Data> = self
+            .items
+            .iter()
+            .filter(|data| data.status == Some(DeleteStatus::Done))
+            .collect();
+        let items_to_remove: HashSet<PathBuf> = done
+            .iter()
+            .flat_map(|data| std::iter::once(&data.path).chain(&data.nested))
+            .cloned()
+            .collect();
+        let deleted: Vec<(PathBuf, u64)> = done
+            .iter()
+            .map(|data| (data.path.clone(), data.bytes))
+            .collect();
+        if !self.demo
+            && let Err(error) = log_deletions(&deleted)
+        {
+            self.command_error = Some(format!("could not write the deletion log: {error}"));
+        }
+        let expected = deleted.iter().map(|(_, bytes)| bytes).sum();
+        let reclaimed = self.space_check.take().map_or(0, |space| space.reclaimed());
+        let failed = match deletion.failed {
+            0 => String::new(),
+            1 => String::from(" | 1 directory could not be deleted"),
+            n => format!(" | {n} directories could not be deleted"),
+        };
+        let output = deletion
+            .output
+            .map(|line| format!(" | {line}"))
+            .unwrap_or_default();
+        self.notice = Some(format!(
+            " {} | {status}{failed}{output} ",
+            space::summary(expected, reclaimed)
+        ));
+
+        if let Some(expanded) = self.expanded_items.as_mut() {
+            expanded.retain(|data| !items_to_remove.contains(&data.path));
+        }
+    }
+
+    pub fn exit_summary(&self) -> Option<String> {
+        (self.deleted_dirs > 0).then(|| {
+            format!(
+                "Deleted {} directories: {} files, {} in {:.1?} ({})",
+                self.deleted_dirs,
+                format_count(self.deleted_files),
+                format_bytes(self.deleted_bytes),
+                self.deleting_time,
+                throughput(self.deleted_files, self.deleted_bytes, self.deleting_time)
+            )
         })
-        .collect()
-}
+    }
 
-fn constraint_len_calculator(items: &[Data]) -> (u16, u16, u16) {
-    let name_len = items
-        .par_iter()
-        .map(Data::name)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let parent_len = items
-        .par_iter()
-        .map(Data::size_as_bytesize)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let selected_len = items
-        .par_iter()
-        .map(Data::select)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
+    pub fn toggle_nested(&mut self) {
+        if self.busy_deleting() {
+            return;
+        }
+        self.prune_deleted();
+        let selected = self.selected_paths();
+        let items = match self.expanded_items.take() {
+            Some(expanded) => expanded,
+            None => {
+                let collapsed = collapse_nested(&self.items);
+                self.expanded_items = Some(std::mem::take(&mut self.items));
+                collapsed
+            }
+        };
+        self.replace_items(items, &selected);
+    }
 
-    #[allow(clippy::cast_possible_truncation)]
-    (selected_len as u16, name_len as u16, parent_len as u16)
-}
+    // Rows that don't match stay hidden, with their selection, until the filter
+    // changes. Filtering always works on the expanded rows.
+    pub fn apply_filter(&mut self, query: &str) {
+        if self.busy_deleting() {
+            return;
+        }
+        if self.expanded_items.is_some() {
+            self.toggle_nested();
+        }
+        self.prune_deleted();
+        let mut selected = self.selected_paths();
+        selected.extend(
+            self.hidden_items
+                .iter()
+                .filter(|data| data.selected)
+                .map(|data| data.path.clone()),
+        );
+        let (visible, hidden) = std::mem::take(&mut self.items)
+            .into_iter()
+            .chain(std::mem::take(&mut self.hidden_items))
+            .partition(|data| self.shows(query, data));
+        self.hidden_items = hidden;
+        self.last_filter = query.to_string();
+        self.replace_items(visible, &selected);
+    }
 
-#[cfg(test)]
-mod tests {
-    use crate::model::Data;
+    fn shows(&self, query: &str, data: &Data) -> bool {
+        let bookmarked = self.bookmarks.contains(&data.path);
+        self.kind_tab.is_none_or(|kind| data.kind == kind)
+            && filter_matches(query, data, self.tags.get(&data.path), bookmarked)
+    }
 
-    use super::constraint_len_calculator;
+    // "All" followed by every kind that was found, largest first, with the
+    // directory count and total size of each.
+    fn kind_tabs(&self) -> Vec<(Option<ArtifactKind>, usize, u64)> {
+        let mut tabs: Vec<(Option<ArtifactKind>, usize, u64)> = vec![(None, 0, 0)];
+        for data in self.items.iter().chain(&self.hidden_items) {
+            tabs[0].1 += 1;
+            tabs[0].2 += data.bytes;
+            match tabs.iter_mut().find(|(kind, ..)| *kind == Some(data.kind)) {
+                Some(tab) => {
+                    tab.1 += 1;
+                    tab.2 += data.bytes;
+                }
+                None => tabs.push((Some(data.kind), 1, data.bytes)),
+            }
+        }
+        tabs[1..].sort_by_key(|(_, _, bytes)| std::cmp::Reverse(*bytes));
+        tabs
+    }
+
+    pub fn switch_kind_tab(&mut self, forward: bool) {
+        if self.busy_deleting() {
+            return;
+        }
+        let tabs = self.kind_tabs();
+        let count = tabs.len();
+        let current = tabs
+            .iter()
+            .position(|(kind, ..)| *kind == self.kind_tab)
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % count
+        } else {
+            (current + count - 1) % count
+        };
+        self.kind_tab = tabs[next].0;
+        let query = self.last_filter.clone();
+        self.apply_filter(&query);
+    }
+
+    pub fn set_selection_where(&mut self, criteria: &Criteria, select: bool) {
+        for data in &mut self.items {
+            if !criteria.matches(data, self.tags.get(&data.path)) {
+                continue;
+            }
+            if select && (data.is_recent(self.safety_window) || data.locked) {
+                continue;
+            }
+            data.selected = select;
+        }
+    }
+
+    // Returns true when the command asks to quit.
+    pub fn run_command(&mut self, input: &str) -> bool {
+        match palette::parse(input) {
+            Ok(Action::Select(criteria)) => {
+                self.set_selection_where(&criteria, true);
+                self.last_criteria = input.trim().split_once(' ').unwrap_or_default().1.into();
+            }
+            Ok(Action::Deselect(criteria)) => self.set_selection_where(&criteria, false),
+            Ok(Action::Filter(query)) => self.apply_filter(&query),
+            Ok(Action::Preset(name)) => self.apply_preset(&name),
+            Ok(Action::SavePreset(name)) => self.save_preset(name),
+            Ok(Action::Tag(tag)) => self.tag_rows(&tag, true),
+            Ok(Action::Untag(tag)) => self.tag_rows(&tag, false),
+            Ok(Action::Note(note)) => self.set_note(note),
+            Ok(Action::Sort(field, reversed)) => {
+                self.sorted_by = field;
+                self.sort_reversed = reversed;
+                self.sort_items();
+            }
+            Ok(Action::AddRoot(path)) => self.add_root(path),
+            Ok(Action::RemoveRoot(path)) => self.remove_root(&path),
+            Ok(Action::ExportSelection(file)) => self.export_selection(&file),
+            Ok(Action::ImportSelection(file)) => self.import_selection(&file),
+            Ok(Action::Suggest) => self.suggest(),
+            Ok(Action::Delete) => self.request_deletion(),
+            Ok(Action::Quit) => return true,
+            Err(message) => self.command_error = Some(message),
+        }
+        false
+    }
+
+    // Selects the obvious wins by the `suggest` rule and says why, for review before
+    // anything is deleted.
+    pub fn suggest(&mut self) {
+        let mut rule = self.config.suggest;
+        if self.demo {
+            rule.clean_git = false;
+        }
+        let window = self.safety_window;
+        let candidates: Vec<Data> = self
+            .items
+            .iter()
+            .filter(|data| data.status.is_none() && !data.locked && !data.is_recent(window))
+            .cloned()
+            .collect();
+        self.suggestions = suggest(&candidates, &rule, SystemTime::now())
+            .into_iter()
+            .collect();
+        let mut bytes = 0;
+        for data in &mut self.items {
+            if self.suggestions.contains_key(&data.path) {
+                data.selected = true;
+                bytes += data.bytes;
+            }
+        }
+        self.notice = Some(match self.suggestions.len() {
+            0 => " Nothing to suggest ".to_string(),
+            n => format!(
+                " Suggested {n} rows ({}), review them before deleting ",
+                format_bytes(bytes)
+            ),
+        });
+    }
+
+    // One path per line, so a colleague can review the plan before it's imported
+    // and deleted.
+    pub fn export_selection(&mut self, file: &Path) {
+        let hidden = self.hidden_items.iter().filter(|data| data.selected);
+        let mut paths: Vec<PathBuf> = hidden
+            .flat_map(|data| std::iter::once(&data.path).chain(&data.nested))
+            .cloned()
+            .chain(self.selected_paths())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        match write_paths(file, &paths) {
+            Ok(()) => {
+                self.notice = Some(format!(
+                    " Exported {} paths to {} ",
+                    paths.len(),
+                    self.show_path(file)
+                ));
+            }
+            Err(error) => {
+                self.command_error = Some(format!("could not write {}: {error}", file.display()));
+            }
+        }
+    }
+
+    // Replaces the selection with exactly the listed paths, filtered-out rows
+    // included. Lines starting with # are comments. Nested rows are expanded
+    // first so each path maps to its own row.
+    pub fn import_selection(&mut self, file: &Path) {
+        if self.busy_deleting() {
+            return;
+        }
+        let contents = match fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(error) => {
+                self.command_error = Some(format!("could not read {}: {error}", file.display()));
+                return;
+            }
+        };
+        let wanted: HashSet<PathBuf> = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(PathBuf::from)
+            .collect();
+        if self.expanded_items.is_some() {
+            self.toggle_nested();
+        }
+        let mut found = 0;
+        for data in self.items.iter_mut().chain(&mut self.hidden_items) {
+            data.selected = wanted.contains(&data.path) && data.status != Some(DeleteStatus::Done);
+            found += usize::from(data.selected);
+        }
+        let missing = match wanted.len() - found {
+            0 => String::new(),
+            n => format!(", {n} not found in this scan"),
+        };
+        let hidden = self
+            .hidden_items
+            .iter()
+            .filter(|data| data.selected)
+            .count();
+        let hidden = match hidden {
+            0 => String::new(),
+            n => format!(", {n} hidden by the filter"),
+        };
+        self.notice = Some(format!(
+            " Selected {found} of {} paths from {}{missing}{hidden} ",
+            wanted.len(),
+            self.show_path(file)
+        ));
+    }
+
+    // Scans the new root in the background; its rows are merged in by
+    // `poll_root_scans` once sized.
+    pub fn add_root(&mut self, path: PathBuf) {
+        if self.demo {
+            self.command_error = Some("demo mode doesn't scan real directories".to_string());
+            return;
+        }
+        let path = match path.canonicalize() {
+            Ok(path) if path.is_dir() => path,
+            Ok(path) => {
+                self.command_error = Some(format!("{} is not a directory", path.display()));
+                return;
+            }
+            Err(error) => {
+                self.command_error = Some(format!("{}: {error}", path.display()));
+                return;
+            }
+        };
+        if let Some(root) = self.roots.iter().find(|root| path.starts_with(root)) {
+            self.command_error = Some(format!("{} is already scanned", self.show_path(root)));
+            return;
+        }
+        let config = Config {
+            root: Some(path.clone()),
+            all_users: false,
+            all_mounts: false,
+            progress_json: false,
+            top: None,
+            ..self.config.clone()
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(scan_silently(&config));
+        });
+        self.roots.push(path.clone());
+        self.root_scans.push((path, receiver));
+    }
+
+    // Drops the root's rows unless another root still covers them.
+    pub fn remove_root(&mut self, path: &Path) {
+        if self.busy_deleting() {
+            return;
+        }
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if !self.roots.contains(&path) {
+            self.command_error = Some(format!("{} is not a scan root", path.display()));
+            return;
+        }
+        if self.roots.len() == 1 {
+            self.command_error = Some("the last root can't be removed".to_string());
+            return;
+        }
+        if self.expanded_items.is_some() {
+            self.toggle_nested();
+        }
+        self.prune_deleted();
+        self.roots.retain(|root| *root != path);
+        self.root_scans.retain(|(root, _)| *root != path);
+        if let Some(guard) = self.root_guard.as_mut() {
+            guard.retain(|root| *root != path);
+        }
+        let roots = self.roots.clone();
+        let keep = |data: &Data| {
+            !data.path.starts_with(&path) || roots.iter().any(|root| data.path.starts_with(root))
+        };
+        self.hidden_items.retain(keep);
+        let selected = self.selected_paths();
+        let items = std::mem::take(&mut self.items)
+            .into_iter()
+            .filter(keep)
+            .collect();
+        self.replace_items(items, &selected);
+        self.notice = Some(format!(" Removed {} ", self.show_path(&path)));
+    }
+
+    // Results wait in their channels until a running deletion is done.
+    pub fn poll_root_scans(&mut self) {
+        if self.deletion.is_some() {
+            return;
+        }
+        let mut finished = Vec::new();
+        self.root_scans
+            .retain(|(root, receiver)| match receiver.try_recv() {
+                Ok(result) => {
+                    finished.push((root.clone(), result));
+                    false
+                }
+                Err(TryRecvError::Empty) => true,
+                Err(TryRecvError::Disconnected) => {
+                    let source = io::Error::other("the scan stopped unexpectedly");
+                    let error = CleanerError::Scan {
+                        path: root.clone(),
+                        source,
+                    };
+                    finished.push((root.clone(), Err(error)));
+                    false
+                }
+            });
+        for (root, result) in finished {
+            match result {
+                Ok((data, _)) => {
+                    // Only a root that actually scanned widens what root may delete.
+                    if let Some(guard) = self.root_guard.as_mut() {
+                        guard.push(root.clone());
+                    }
+                    self.merge_scanned(&root, data);
+                }
+                Err(error) => {
+                    self.roots.retain(|known| *known != root);
+                    self.command_error = Some(error.to_string());
+                }
+            }
+        }
+    }
+
+    fn merge_scanned(&mut self, root: &Path, mut data: Vec<Data>) {
+        if self.expanded_items.is_some() {
+            self.toggle_nested();
+        }
+        self.prune_deleted();
+        compare_with_history(&mut data, &self.history);
+        let known: HashSet<PathBuf> = self
+            .items
+            .iter()
+            .chain(&self.hidden_items)
+            .map(|data| data.path.clone())
+            .collect();
+        data.retain(|data| !known.contains(&data.path));
+        let bytes: u64 = data.iter().map(|data| data.bytes).sum();
+        self.notice = Some(format!(
+            " Found {} directories ({}) under {} ",
+            format_count(data.len() as u64),
+            format_bytes(bytes),
+            self.show_path(root)
+        ));
+        let (visible, hidden): (Vec<Data>, Vec<Data>) = data
+            .into_iter()
+            .partition(|data| self.shows(&self.last_filter, data));
+        self.hidden_items.extend(hidden);
+        let selected = self.selected_paths();
+        let current = self.state.selected();
+        let mut items = std::mem::take(&mut self.items);
+        items.extend(visible);
+        self.replace_items(items, &selected);
+        if let Some(i) = current {
+            self.select_row(i.min(self.items.len().saturating_sub(1)));
+        }
+    }
+
+    pub fn open_root_menu(&mut self) {
+        self.root_menu = Some(ListState::default().with_selected(Some(0)));
+    }
+
+    pub fn remove_highlighted_root(&mut self) {
+        let Some(root) = self
+            .root_menu
+            .as_ref()
+            .and_then(ListState::selected)
+            .and_then(|i| self.roots.get(i))
+            .cloned()
+        else {
+            return;
+        };
+        self.remove_root(&root);
+        if let Some(menu) = self.root_menu.as_mut() {
+            menu.select(Some(menu.selected().unwrap_or(0).min(self.roots.len() - 1)));
+        }
+    }
+
+    pub fn apply_preset(&mut self, name: &str) {
+        if self.busy_deleting() {
+            return;
+        }
+        let Some(preset) = load_presets()
+            .into_iter()
+            .find(|preset| preset.name == name)
+        else {
+            self.command_error = Some(format!("no preset named '{name}'"));
+            return;
+        };
+        match Criteria::parse(&preset.criteria) {
+            Ok(criteria) => {
+                self.apply_filter(&preset.filter);
+                self.set_selection_where(&criteria, true);
+                self.last_criteria = preset.criteria;
+            }
+            Err(message) => self.command_error = Some(format!("preset '{name}': {message}")),
+        }
+    }
+
+    // Remembers the last filter and select criteria under a name.
+    pub fn save_preset(&mut self, name: String) {
+        let mut presets = load_presets();
+        presets.retain(|preset| preset.name != name);
+        presets.push(Preset {
+            name,
+            filter: self.last_filter.clone(),
+            criteria: self.last_criteria.clone(),
+        });
+        if !self.demo
+            && let Err(error) = save_presets(&presets)
+        {
+            self.command_error = Some(format!("could not save presets: {error}"));
+        }
+    }
+
+    // Applies to the checked rows, or the highlighted row when nothing is checked.
+    pub fn set_note(&mut self, note: String) {
+        let Some(data) = self.state.selected().and_then(|i| self.items.get(i)) else {
+            return;
+        };
+        if note.is_empty() {
+            self.notes.remove(&data.path);
+        } else {
+            self.notes.insert(data.path.clone(), note);
+        }
+        if !self.demo
+            && let Err(error) = save_notes(&self.notes)
+        {
+            self.command_error = Some(format!("could not save notes: {error}"));
+        }
+    }
+
+    pub fn toggle_bookmark(&mut self) {
+        let Some(data) = self.state.selected().and_then(|i| self.items.get(i)) else {
+            return;
+        };
+        if !self.bookmarks.remove(&data.path) {
+            self.bookmarks.insert(data.path.clone());
+        }
+        if !self.demo
+            && let Err(error) = save_bookmarks(&self.bookmarks)
+        {
+            self.command_error = Some(format!("could not save bookmarks: {error}"));
+        }
+    }
+
+    pub fn toggle_bookmarks_filter(&mut self) {
+        if self.last_filter == "is:bookmarked" {
+            self.apply_filter("");
+        } else {
+            self.apply_filter("is:bookmarked");
+        }
+    }
+
+    pub fn tag_rows(&mut self, tag: &str, add: bool) {
+        let mut targets: Vec<PathBuf> = self
+            .items
+            .iter()
+            .filter(|data| data.selected)
+            .map(|data| data.path.clone())
+            .collect();
+        if targets.is_empty() {
+            targets.extend(
+                self.state
+                    .selected()
+                    .and_then(|i| self.items.get(i))
+                    .map(|data| data.path.clone()),
+            );
+        }
+        for path in targets {
+            let tags = self.tags.entry(path).or_default();
+            if add {
+                tags.insert(tag.to_string());
+            } else {
+                tags.remove(tag);
+            }
+        }
+        self.tags.retain(|_, tags| !tags.is_empty());
+        if !self.demo
+            && let Err(error) = save_tags(&self.tags)
+        {
+            self.command_error = Some(format!("could not save tags: {error}"));
+        }
+    }
+
+    // Re-sizes the rows still listed without walking the whole root again; rows
+    // whose directories disappeared in the meantime are dropped.
+    pub fn refresh_sizes(&mut self) {
+        if self.deletion.is_some() {
+            return;
+        }
+        if self.demo {
+            self.notice = Some(DEMO_NOTICE.to_string());
+            return;
+        }
+        self.prune_deleted();
+        let before: u64 = self.items.iter().map(|data| data.bytes).sum();
+        let current = self
+            .state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .map(|data| data.path.clone());
+        let selected = self.selected_paths();
+
+        let mut items = std::mem::take(&mut self.items);
+        items.retain(|data| data.path.exists());
+        items.par_iter_mut().for_each(resize);
+        if let Some(expanded) = self.expanded_items.as_mut() {
+            expanded.retain(|data| data.path.exists());
+            expanded.par_iter_mut().for_each(resize);
+        }
+        self.replace_items(items, &selected);
+        if let Some(i) =
+            current.and_then(|path| self.items.iter().position(|data| data.path == path))
+        {
+            self.select_row(i);
+        }
+
+        let after: u64 = self.items.iter().map(|data| data.bytes).sum();
+        self.notice = Some(format!(
+            " Refreshed {} rows: {} → {} ",
+            self.items.len(),
+            format_bytes(before),
+            format_bytes(after)
+        ));
+    }
+
+    fn selected_bytes(&self) -> u64 {
+        self.items
+            .iter()
+            .filter(|data| data.selected)
+            .map(|data| data.bytes)
+            .sum()
+    }
+
+    fn selected_paths(&self) -> HashSet<PathBuf> {
+        self.items
+            .iter()
+            .filter(|data| data.selected)
+            .flat_map(|data| std::iter::once(&data.path).chain(&data.nested))
+            .cloned()
+            .collect()
+    }
+
+    // Carries the selection over by path; a selected aggregate row selects
+    // everything nested under it.
+    fn replace_items(&mut self, items: Vec<Data>, selected: &HashSet<PathBuf>) {
+        self.items = items;
+        self.sort_items();
+        for data in &mut self.items {
+            data.selected = selected.contains(&data.path);
+        }
+        self.longest_item_lens = constraint_len_calculator(&self.items);
+        self.state.select((!self.items.is_empty()).then_some(0));
+    }
+
+    // Suspends the TUI for an interactive shell in the highlighted row's project and
+    // picks up where it left off once the shell exits.
+    fn open_shell(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        let Some(project) = self
+            .state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .map(|data| data.project.clone())
+        else {
+            return Ok(());
+        };
+        let shell = interactive_shell();
+        terminal::restore();
+        println!(
+            "{} (exit the shell to return)",
+            display_path(&project, self.path_display == PathDisplay::Absolute)
+        );
+        let status = process::Command::new(&shell).current_dir(&project).status();
+        *terminal = terminal::init()?;
+        terminal.clear()?;
+        if let Err(error) = status {
+            self.notice = Some(format!(" Could not start {shell}: {error} "));
+        }
+        Ok(())
+    }
+
+    pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<Option<String>> {
+        loop {
+            if self.dirty {
+                self.render_frame(&mut terminal)?;
+            }
+
+            // Without input the loop still ticks, so background deletions and root
+            // scans report progress and the spinner keeps turning. A resize only
+            // needs the redraw at the top of the loop.
+            if !event::poll(TICK_RATE)? {
+                self.update(Message::Tick);
+                continue;
+            }
+            match self.handle_event(event::read()?) {
+                Some(Effect::Quit) => {
+                    self.save_session()?;
+                    return Ok(self.exit_summary());
+                }
+                Some(Effect::OpenShell) => self.open_shell(&mut terminal)?,
+                None => {}
+            }
+        }
+    }
+
+    // Drives the UI with scripted events on any backend, drawing a frame before
+    // each one and after the last. Nothing is saved on quit and `!` does nothing,
+    // as there's no real terminal to hand over.
+    pub fn run_with_backend<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        events: impl IntoIterator<Item = Event>,
+    ) -> Result<(), B::Error> {
+        for event in events {
+            self.render_frame(terminal)?;
+            if self.handle_event(event) == Some(Effect::Quit) {
+                break;
+            }
+        }
+        self.render_frame(terminal)
+    }
+
+    pub fn render_frame<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), B::Error> {
+        terminal.draw(|frame| self.draw(frame))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn handle_event(&mut self, event: Event) -> Option<Effect> {
+        match event {
+            Event::Resize(..) => {
+                self.dirty = true;
+                None
+            }
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                if self.command_error.take().is_some() | self.notice.take().is_some() {
+                    self.dirty = true;
+                }
+                let message = self.message_for(key)?;
+                self.update(message)
+            }
+            _ => None,
+        }
+    }
+
+    // A background deletion or root scan keeps the spinner and progress moving.
+    fn animating(&self) -> bool {
+        self.deletion.is_some() || !self.root_scans.is_empty()
+    }
+
+    // The first open prompt, overlay or menu gets the key; otherwise it goes to
+    // the main list.
+    pub fn message_for(&self, key: KeyEvent) -> Option<Message> {
+        let message = if self.command_line.is_some() {
+            match key.code {
+                KeyCode::Esc => Message::CancelCommand,
+                KeyCode::Enter => Message::SubmitCommand,
+                KeyCode::Backspace => Message::CommandBackspace,
+                KeyCode::Char(c) => Message::CommandInput(c),
+                _ => return None,
+            }
+        } else if self.jump_query.is_some() {
+            match key.code {
+                KeyCode::Esc => Message::CancelJump,
+                KeyCode::Enter => Message::ConfirmJump,
+                KeyCode::Backspace => Message::JumpBackspace,
+                KeyCode::Char(c) => Message::JumpInput(c),
+                _ => return None,
+            }
+        } else if self.duplicates.is_some() {
+            match key.code {
+                KeyCode::Esc | KeyCode::Backspace | KeyCode::Char('q' | 'u') => {
+                    Message::CloseOverlay
+                }
+                KeyCode::Char('j') | KeyCode::Down => Message::OverlayNext,
+                KeyCode::Char('k') | KeyCode::Up => Message::OverlayPrevious,
+                KeyCode::Tab => Message::ToggleSavings,
+                _ => return None,
+            }
+        } else if let Some(groups) = self.groups.as_ref() {
+            match key.code {
+                KeyCode::Esc | KeyCode::Backspace | KeyCode::Char('q' | 'g') => {
+                    Message::CloseOverlay
+                }
+                KeyCode::Char('j') | KeyCode::Down => Message::OverlayNext,
+                KeyCode::Char('k') | KeyCode::Up => Message::OverlayPrevious,
+                KeyCode::Char('h') | KeyCode::Left => Message::CollapseGroup,
+                KeyCode::Enter if groups.selected_member().is_some() => Message::ShowGroupMember,
+                KeyCode::Enter | KeyCode::Char(' ' | 'l') | KeyCode::Right => Message::ToggleGroup,
+                _ => return None,
+            }
+        } else if self.drilldown.is_some() {
+            match key.code {
+                KeyCode::Esc | KeyCode::Backspace | KeyCode::Char('q') => Message::CloseOverlay,
+                KeyCode::Char('j') | KeyCode::Down => Message::OverlayNext,
+                KeyCode::Char('k') | KeyCode::Up => Message::OverlayPrevious,
+                KeyCode::Tab => Message::ToggleDrilldownSort,
+                KeyCode::Char('r') => Message::ReverseDrilldown,
+                _ => return None,
+            }
+        } else if self.deletion_warning.is_some() {
+            match key.code {
+                KeyCode::Char('y') => Message::ConfirmDeletion,
+                _ => Message::CancelDeletion,
+            }
+        } else if self.root_menu.is_some() {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q' | 'o') => Message::CloseOverlay,
+                KeyCode::Char('j') | KeyCode::Down => Message::OverlayNext,
+                KeyCode::Char('k') | KeyCode::Up => Message::OverlayPrevious,
+                KeyCode::Char('a') => Message::AddRoot,
+                KeyCode::Char('d') | KeyCode::Delete => Message::RemoveRoot,
+                _ => return None,
+            }
+        } else if self.sort_menu.is_some() {
+            match key.code {
+                KeyCode::Esc | KeyCode::Tab | KeyCode::Char('q') => Message::CloseOverlay,
+                KeyCode::Char('j') | KeyCode::Down => Message::OverlayNext,
+                KeyCode::Char('k') | KeyCode::Up => Message::OverlayPrevious,
+                KeyCode::Enter => Message::ApplySort { reversed: false },
+                KeyCode::Char('r') => Message::ApplySort { reversed: true },
+                KeyCode::Char('s') => Message::ApplySecondarySort { reversed: false },
+                KeyCode::Char('S') => Message::ApplySecondarySort { reversed: true },
+                KeyCode::Char('x') => Message::ClearSecondarySort,
+                _ => return None,
+            }
+        } else {
+            match key.code {
+                KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    Message::OpenJump
+                }
+                KeyCode::Char('q') | KeyCode::Esc => Message::Quit,
+                KeyCode::Char('j') | KeyCode::Down => Message::NextRow,
+                KeyCode::Char('k') | KeyCode::Up => Message::PreviousRow,
+                KeyCode::Char('l') | KeyCode::Right => Message::NextColor,
+                KeyCode::Char('h') | KeyCode::Left => Message::PreviousColor,
+                KeyCode::Enter if self.show_preview => Message::OpenDrilldown,
+                KeyCode::Char(' ') if self.keymap == Keymap::Npkill && !self.read_only => {
+                    Message::DeleteHighlighted
+                }
+                KeyCode::Enter | KeyCode::Char(' ') => Message::ToggleSelection,
+                KeyCode::Char('a') => Message::ToggleSelectAll,
+                KeyCode::Char('d') => Message::RequestDeletion,
+                KeyCode::Char('x') => Message::QuickDelete,
+                KeyCode::Char('r') => Message::ReverseSort,
+                KeyCode::Tab => Message::OpenSortMenu,
+                KeyCode::Char('p') => Message::TogglePreview,
+                KeyCode::Char('u') => Message::OpenDuplicates,
+                KeyCode::Char('g') => Message::OpenGroups,
+                KeyCode::Char('o') => Message::OpenRootMenu,
+                KeyCode::Char(']') => Message::SwitchKindTab { forward: true },
+                KeyCode::Char('[') => Message::SwitchKindTab { forward: false },
+                KeyCode::Char('v') => Message::CyclePathDisplay,
+                KeyCode::Char('m') => Message::ToggleNested,
+                KeyCode::Char('!') => Message::OpenShell,
+                KeyCode::Char('c') => Message::ToggleDelta,
+                KeyCode::Char('b') => Message::JumpToLargest,
+                KeyCode::Char('*') => Message::ToggleBookmark,
+                KeyCode::Char('\'') => Message::ToggleBookmarksFilter,
+                KeyCode::Char('f') | KeyCode::F(5) => Message::RefreshSizes,
+                KeyCode::Char(':') => Message::OpenCommandLine,
+                _ => return None,
+            }
+        };
+        Some(message)
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Effect> {
+        self.dirty |= message != Message::Tick || self.animating();
+        match message {
+            Message::Tick => {
+                self.ticks = self.ticks.wrapping_add(1);
+                self.poll_deletion();
+                self.poll_root_scans();
+            }
+            Message::OpenCommandLine => self.command_line = Some(String::new()),
+            Message::CommandInput(c) => {
+                if let Some(input) = self.command_line.as_mut() {
+                    input.push(c);
+                }
+            }
+            Message::CommandBackspace => {
+                if let Some(input) = self.command_line.as_mut() {
+                    input.pop();
+                }
+            }
+            Message::SubmitCommand => {
+                let input = self.command_line.take().unwrap_or_default();
+                if self.run_command(&input) {
+                    return Some(Effect::Quit);
+                }
+            }
+            Message::CancelCommand => self.command_line = None,
+
+            Message::OpenJump => self.open_jump_prompt(),
+            Message::JumpInput(c) => {
+                if let Some(query) = self.jump_query.as_mut() {
+                    query.push(c);
+                    self.jump_to_best_match();
+                }
+            }
+            Message::JumpBackspace => {
+                if let Some(query) = self.jump_query.as_mut() {
+                    query.pop();
+                    self.jump_to_best_match();
+                }
+            }
+            Message::ConfirmJump => self.jump_query = None,
+            Message::CancelJump => self.cancel_jump(),
+
+            Message::CloseOverlay => {
+                if self.duplicates.take().is_none()
+                    && self.groups.take().is_none()
+                    && self.drilldown.take().is_none()
+                    && self.root_menu.take().is_none()
+                {
+                    self.sort_menu = None;
+                }
+            }
+            Message::OverlayNext => self.move_in_overlay(true),
+            Message::OverlayPrevious => self.move_in_overlay(false),
+            Message::ToggleSavings => {
+                if let Some(duplicates) = self.duplicates.as_mut() {
+                    duplicates.toggle_savings();
+                }
+            }
+            Message::ToggleGroup => {
+                if let Some(groups) = self.groups.as_mut() {
+                    groups.toggle();
+                }
+            }
+            Message::CollapseGroup => {
+                if let Some(groups) = self.groups.as_mut() {
+                    groups.collapse();
+                }
+            }
+            Message::ShowGroupMember => self.show_group_member(),
+            Message::ToggleDrilldownSort => {
+                if let Some(drilldown) = self.drilldown.as_mut() {
+                    drilldown.toggle_sort_field();
+                }
+            }
+            Message::ReverseDrilldown => {
+                if let Some(drilldown) = self.drilldown.as_mut() {
+                    drilldown.reverse();
+                }
+            }
+            Message::AddRoot => {
+                self.root_menu = None;
+                self.command_line = Some("root add ".to_string());
+            }
+            Message::RemoveRoot => self.remove_highlighted_root(),
+            Message::ApplySort { reversed } => self.apply_sort_option(reversed),
+            Message::ApplySecondarySort { reversed } => {
+                self.apply_secondary_sort_option(reversed);
+            }
+            Message::ClearSecondarySort => self.clear_secondary_sort(),
+
+            Message::ConfirmDeletion => {
+                self.confirmations_left = self.confirmations_left.saturating_sub(1);
+                if self.confirmations_left == 0 {
+                    self.deletion_warning = None;
+                    self.remove_directories();
+                }
+            }
+            Message::CancelDeletion => {
+                self.deletion_warning = None;
+                self.quick_target = None;
+            }
+
+            Message::NextRow => self.next_row(),
+            Message::PreviousRow => self.previous_row(),
+            Message::NextColor => self.next_color(),
+            Message::PreviousColor => self.previous_color(),
+            Message::ToggleSelection => self.select_for_deletion(),
+            Message::ToggleSelectAll => self.toggle_select_all(),
+            Message::DeleteHighlighted => self.delete_highlighted(),
+            Message::RequestDeletion => self.request_deletion(),
+            Message::QuickDelete => self.quick_delete(),
+            Message::ReverseSort => {
+                self.sort_reversed = !self.sort_reversed;
+                self.sort_items();
+            }
+            Message::OpenSortMenu => self.open_sort_menu(),
+            Message::TogglePreview => self.show_preview = !self.show_preview,
+            Message::OpenDrilldown => self.open_drilldown(),
+            Message::OpenDuplicates => self.open_duplicates(),
+            Message::OpenGroups => self.open_groups(),
+            Message::OpenRootMenu => self.open_root_menu(),
+            Message::SwitchKindTab { forward } => self.switch_kind_tab(forward),
+            Message::CyclePathDisplay => self.path_display = self.path_display.next(),
+            Message::ToggleNested => self.toggle_nested(),
+            Message::ToggleDelta => self.show_delta = !self.show_delta,
+            Message::JumpToLargest => self.jump_to_largest(),
+            Message::ToggleBookmark => self.toggle_bookmark(),
+            Message::ToggleBookmarksFilter => self.toggle_bookmarks_filter(),
+            Message::RefreshSizes => self.refresh_sizes(),
+            Message::OpenShell => return Some(Effect::OpenShell),
+            Message::Quit if self.busy_deleting() => {}
+            Message::Quit => return Some(Effect::Quit),
+        }
+        None
+    }
+
+    fn move_in_overlay(&mut self, forward: bool) {
+        if let Some(duplicates) = self.duplicates.as_mut() {
+            if forward {
+                duplicates.next_row();
+            } else {
+                duplicates.previous_row();
+            }
+        } else if let Some(groups) = self.groups.as_mut() {
+            if forward {
+                groups.next_row();
+            } else {
+                groups.previous_row();
+            }
+        } else if let Some(drilldown) = self.drilldown.as_mut() {
+            if forward {
+                drilldown.next_row();
+            } else {
+                drilldown.previous_row();
+            }
+        } else if let Some(menu) = self.root_menu.as_mut() {
+            let count = self.roots.len();
+            let i = menu.selected().unwrap_or(0);
+            let next = if forward { i + 1 } else { i + count - 1 };
+            menu.select(Some(next % count));
+        } else if forward {
+            self.next_sort_option();
+        } else {
+            self.previous_sort_option();
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        self.narrow = frame.area().width < NARROW_WIDTH;
+        let footer_height = if self.narrow { 3 } else { 4 };
+        let vertical = &Layout::vertical([Constraint::Min(5), Constraint::Length(footer_height)]);
+        let rects = vertical.split(frame.area());
+
+        self.set_colors();
+
+        let (table_area, preview_area) = if self.show_preview && !self.narrow {
+            let [table, preview] = Layout::horizontal([
+                Constraint::Percentage(100 - PREVIEW_PERCENTAGE),
+                Constraint::Percentage(PREVIEW_PERCENTAGE),
+            ])
+            .areas(rects[0]);
+            (table, Some(preview))
+        } else {
+            (rects[0], None)
+        };
+
+        let tabs = self.kind_tabs();
+        // Tabs only help once more than one kind of artifact was found.
+        let table_area = if tabs.len() > 2 {
+            let [tab_area, table_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(table_area);
+            self.render_kind_tabs(frame, tab_area, &tabs);
+            table_area
+        } else {
+            table_area
+        };
+        if self.duplicates.is_some() {
+            self.render_duplicates(frame, table_area);
+        } else if self.groups.is_some() {
+            self.render_groups(frame, table_area);
+        } else if self.drilldown.is_some() {
+            self.render_drilldown(frame, table_area);
+        } else {
+            self.render_table(frame, table_area);
+            self.render_scrollbar(frame, table_area);
+        }
+        if let Some(area) = preview_area {
+            self.render_preview(frame, area);
+        }
+        self.render_footer(frame, rects[1]);
+        self.render_sort_menu(frame, rects[0]);
+        self.render_root_menu(frame, rects[0]);
+        self.render_deletion_warning(frame, rects[0]);
+    }
+
+    fn format_size(&self, bytes: u64) -> String {
+        format_size(ByteSize::b(bytes), self.narrow)
+    }
+
+    fn render_table(&mut self, frame: &mut Frame, area: Rect) {
+        let header_style = Style::default()
+            .fg(self.colors.header_fg)
+            .bg(self.colors.header_bg);
+        let selected_row_style = Style::default()
+            .add_modifier(Modifier::REVERSED)
+            .fg(self.colors.selected_row_style_fg);
+        let selected_col_style = Style::default().fg(self.colors.selected_column_style_fg);
+        let selected_cell_style = Style::default()
+            .add_modifier(Modifier::REVERSED)
+            .fg(self.colors.selected_cell_style_fg);
+
+        let sort_arrow = if self.sort_reversed { "↓" } else { "↑" };
+        let total = self.total_bytes();
+        let mut selected_header = if self.narrow { "Sel" } else { "Selected" }.to_string();
+        if self.sorted_by == SortField::Selected {
+            selected_header.push(' ');
+            selected_header.push_str(sort_arrow);
+        }
+        let selected_bytes = self.selected_bytes();
+        if selected_bytes > 0 {
+            selected_header.push_str(&format!("\n{}", self.format_size(selected_bytes)));
+        }
+        let mut name_header = self.path_display.label().to_string();
+        if self.sorted_by == SortField::Name {
+            name_header.push(' ');
+            name_header.push_str(sort_arrow);
+        }
+        let mut size_header = "Size".to_string();
+        if self.sorted_by == SortField::Size {
+            size_header.push(' ');
+            size_header.push_str(sort_arrow);
+        }
+        let mut headers = vec![selected_header, name_header];
+        if !self.narrow {
+            if self.all_users {
+                headers.push("User".to_string());
+            }
+            headers.push("Type".to_string());
+            headers.push("Trend".to_string());
+            if self.show_delta {
+                headers.push("Δ Size".to_string());
+            }
+            headers.push(String::new());
+            let mut share_header = "Share".to_string();
+            if selected_bytes > 0 {
+                share_header.push('\n');
+                share_header.push_str(&format_share(selected_bytes, total));
+            }
+            headers.push(share_header);
+        }
+        headers.push(size_header);
+        let column_count = headers.len();
+        let header = headers
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(header_style)
+            .height(TABLE_HEADER_HEIGHT);
+        let footer = self.totals_row(column_count).style(header_style);
+        let columns = self.column_widths;
+        let widths = if self.narrow {
+            vec![
+                Constraint::Length(5),
+                columns.name.constraint(Constraint::Fill(1)),
+                columns.size.constraint(Constraint::Length(8)),
+            ]
+        } else {
+            let mut widths = vec![
+                Constraint::Length(10),
+                columns.name.constraint(Constraint::Fill(1)),
+            ];
+            if self.all_users {
+                widths.push(columns.user.constraint(Constraint::Length(USER_WIDTH)));
+            }
+            widths.extend([
+                columns.kind.constraint(Constraint::Length(TYPE_WIDTH)),
+                columns.trend.constraint(Constraint::Length(TREND_WIDTH)),
+            ]);
+            if self.show_delta {
+                widths.push(columns.delta.constraint(Constraint::Length(DELTA_WIDTH)));
+            }
+            widths.extend([
+                columns.bar.constraint(Constraint::Length(BAR_WIDTH)),
+                columns.share.constraint(Constraint::Length(SHARE_WIDTH)),
+                columns
+                    .size
+                    .constraint(Constraint::Length(self.longest_item_lens.2 + 1)),
+            ]);
+            widths
+        };
+        // Laid out the way the table will, less the column the scrollbar covers.
+        let laid_out = Layout::horizontal(widths.clone())
+            .spacing(1)
+            .split(Rect::new(0, 0, area.width.saturating_sub(1), 1));
+        let name_width = usize::from(laid_out[1].width);
+        let bar_width = usize::from(laid_out[laid_out.len().saturating_sub(3)].width);
+        let largest = self.items.iter().map(|data| data.bytes).max().unwrap_or(0);
+
+        // Only the rows on screen are built, so a whole-disk scan with tens of
+        // thousands of rows redraws as fast as a small one.
+        let visible = self.visible_rows(area);
+        let offset = window_offset(
+            self.state.offset(),
+            self.state.selected(),
+            visible,
+            self.items.len(),
+        );
+        let start = offset.saturating_sub(RENDER_MARGIN);
+        let end = (offset + visible + RENDER_MARGIN).min(self.items.len());
+        for data in &self.items[start..end] {
+            if !self.trends.contains_key(&data.path) {
+                let trend = self
+                    .history
+                    .get(&data.path)
+                    .map(|samples| {
+                        let sizes: Vec<u64> = samples.iter().map(|(_, bytes)| *bytes).collect();
+                        sparkline(&sizes)
+                    })
+                    .unwrap_or_default();
+                self.trends.insert(data.path.clone(), trend);
+            }
+        }
+        let rows = self.items[start..end].iter().enumerate().map(|(i, data)| {
+            let i = start + i;
+            let color = match i % 2 {
+                0 => self.colors.normal_row_color,
+                _ => self.colors.alt_row_color,
+            };
+            let size = if self.narrow {
+                self.format_size(data.bytes)
+            } else {
+                data.size()
+            };
+            let name = data.display_as(self.path_display);
+            let name = name.as_str();
+            let mut suffix = data
+                .kind
+                .badge()
+                .map(|badge| format!(" [{badge}]"))
+                .unwrap_or_default();
+            if !data.nested.is_empty() {
+                suffix.push_str(&format!(" (+{} nested)", data.nested.len()));
+            }
+            if data.cloud_files > 0 || in_cloud_sync(&data.path) {
+                suffix.push_str(" [☁ cloud]");
+            }
+            if data.orphan {
+                suffix.push_str(" [orphan]");
+            }
+            if data.locked {
+                suffix.push_str(" 🔒");
+            }
+            if self.bookmarks.contains(&data.path) {
+                suffix.push_str(" ★");
+            }
+            if self.suggestions.contains_key(&data.path) {
+                suffix.push_str(" [suggested]");
+            }
+            if self.notes.contains_key(&data.path) {
+                suffix.push_str(" ✎");
+            }
+            if let Some(twins) = twins_of(&self.lockfile_twins, &data.path) {
+                suffix.push_str(&format!(" [same lockfile ×{}]", twins.artifacts.len()));
+            }
+            for tag in self.tags.get(&data.path).into_iter().flatten() {
+                suffix.push_str(&format!(" #{tag}"));
+            }
+            if let Some(DeleteStatus::Failed(error)) = &data.status {
+                suffix.push_str(&format!(" ({error})"));
+            }
+            let name = truncate_middle(name, name_width.saturating_sub(suffix.width()));
+            let mut name = highlight_matches(&name, &self.search_positions(&name));
+            name.push_span(suffix);
+            let mut style = Style::new().bg(color);
+            style = match &data.status {
+                Some(DeleteStatus::Done) => style
+                    .fg(tailwind::SLATE.c500)
+                    .add_modifier(Modifier::CROSSED_OUT),
+                Some(DeleteStatus::Failed(_)) => style.fg(tailwind::RED.c400),
+                Some(DeleteStatus::Deleting) => style.fg(tailwind::AMBER.c300),
+                _ if self.critical_size.is_some_and(|size| data.bytes >= size) => {
+                    style.fg(tailwind::RED.c500).add_modifier(Modifier::BOLD)
+                }
+                _ if self.warn_size.is_some_and(|size| data.bytes >= size) => {
+                    style.fg(tailwind::ORANGE.c400)
+                }
+                _ if self.is_recent(data) => style.fg(tailwind::SLATE.c500),
+                _ if data.orphan => style.fg(tailwind::LIME.c300).add_modifier(Modifier::ITALIC),
+                _ => style.fg(self.colors.row_fg),
+            };
+            let checkbox = match &data.status {
+                Some(DeleteStatus::Deleting) => format!("  {}", self.spinner()),
+                Some(status) => status.symbol().to_string(),
+                None => checkbox(data.selected).to_string(),
+            };
+            let trend = self.trends.get(&data.path).cloned().unwrap_or_default();
+            let delta = format_delta(data.bytes, data.previous_bytes);
+            let mut cells = vec![Line::from(checkbox), name];
+            if !self.narrow {
+                if self.all_users {
+                    cells.push(Line::from(data.user.clone().unwrap_or_default()));
+                }
+                cells.push(Line::from(data.project_type.badge()));
+                cells.push(Line::from(trend));
+                if self.show_delta {
+                    cells.push(Line::from(delta));
+                }
+            }
+            let mut size = Line::from(size);
+            let mut bar = Line::from(size_bar(data.bytes, largest, bar_width));
+            if let Some(heatmap) = self.heatmap
+                && data.status.is_none()
+                && !self.is_recent(data)
+            {
+                let color = heatmap.color(data.bytes, largest);
+                size = size.fg(color);
+                bar = bar.fg(color);
+            }
+            if !self.narrow {
+                cells.push(bar);
+                cells.push(Line::from(format_share(data.bytes, total)));
+            }
+            cells.push(size);
+            let compact = self.row_height == COMPACT_ITEM_HEIGHT;
+            cells
+                .into_iter()
+                .map(|line| {
+                    if compact {
+                        Cell::from(line)
+                    } else {
+                        Cell::from(Text::from(vec![Line::default(), line]))
+                    }
+                })
+                .collect::<Row>()
+                .style(style)
+                .height(self.row_height as u16)
+        });
+        let bar = "";
+        let highlight_symbol = if self.row_height == COMPACT_ITEM_HEIGHT {
+            Text::from(bar)
+        } else {
+            Text::from(vec!["".into(), bar.into(), bar.into(), "".into()])
+        };
+        let t = Table::new(rows, widths)
+            .header(header)
+            .footer(footer)
+            .row_highlight_style(selected_row_style)
+            .column_highlight_style(selected_col_style)
+            .cell_highlight_style(selected_cell_style)
+            .highlight_symbol(highlight_symbol)
+            .bg(self.colors.buffer_bg)
+            .highlight_spacing(HighlightSpacing::Always);
+        let mut window = TableState::default()
+            .with_offset(offset - start)
+            .with_selected(self.state.selected().map(|i| i.saturating_sub(start)));
+        frame.render_stateful_widget(t, area, &mut window);
+        *self.state.offset_mut() = start + window.offset();
+    }
+
+    fn visible_rows(&self, area: Rect) -> usize {
+        let chrome = TABLE_HEADER_HEIGHT + TABLE_FOOTER_HEIGHT;
+        usize::from(area.height.saturating_sub(chrome)) / self.row_height
+    }
+
+    // Pinned below the rows: what the listed rows add up to, and the selection.
+    fn totals_row(&self, column_count: usize) -> Row<'static> {
+        let listed: u64 = self.items.iter().map(|data| data.bytes).sum();
+        let selected = self.items.iter().filter(|data| data.selected).count();
+        let mut cells = vec![Text::default(); column_count];
+        cells[0] = Text::from("Total");
+        cells[1] = Text::from(vec![
+            Line::from(format!("{} listed", format_count(self.items.len() as u64))),
+            Line::from(format!("{} selected", format_count(selected as u64))),
+        ]);
+        cells[column_count - 1] = Text::from(vec![
+            Line::from(self.format_size(listed)),
+            Line::from(self.format_size(self.selected_bytes())),
+        ]);
+        Row::new(cells).height(TABLE_FOOTER_HEIGHT)
+    }
+
+    // Everything discovered, including rows a filter is hiding.
+    fn total_bytes(&self) -> u64 {
+        self.items
+            .iter()
+            .chain(&self.hidden_items)
+            .map(|data| data.bytes)
+            .sum()
+    }
+
+    // What to highlight in a row's name: the jump query's fuzzy match while jumping,
+    // otherwise the active path filter.
+    fn search_positions(&self, name: &str) -> Vec<usize> {
+        match self.jump_query.as_deref() {
+            Some(query) if !query.is_empty() => fuzzy_positions(query, name).unwrap_or_default(),
+            _ if self.last_filter.contains(':') => Vec::new(),
+            _ => substring_positions(&self.last_filter, name),
+        }
+    }
+
+    fn render_drilldown(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(drilldown) = self.drilldown.as_mut() else {
+            return;
+        };
+
+        let header_style = Style::default()
+            .fg(self.colors.header_fg)
+            .bg(self.colors.header_bg);
+        let selected_row_style = Style::default()
+            .add_modifier(Modifier::REVERSED)
+            .fg(self.colors.selected_row_style_fg);
+
+        let sort_arrow = if drilldown.sort_reversed {
+            "↑"
+        } else {
+            "↓"
+        };
+        let (mut name_header, mut size_header) = ("Package".to_string(), "Size".to_string());
+        if drilldown.sort_by_name {
+            name_header = format!("{name_header} {sort_arrow}");
+        } else {
+            size_header = format!("{size_header} {sort_arrow}");
+        }
+        let header = [name_header, size_header]
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(header_style);
+
+        let rows = drilldown
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(i, (name, bytes))| {
+                let color = match i % 2 {
+                    0 => self.colors.normal_row_color,
+                    _ => self.colors.alt_row_color,
+                };
+                Row::new([name.clone(), format_bytes(*bytes)])
+                    .style(Style::new().fg(self.colors.row_fg).bg(color))
+            });
+
+        let title = format!(
+            " {} — {} packages, {} ",
+            drilldown.title,
+            drilldown.packages.len(),
+            format_bytes(drilldown.total())
+        );
+        let t = Table::new(rows, [Constraint::Fill(1), Constraint::Length(12)])
+            .header(header)
+            .row_highlight_style(selected_row_style)
+            .bg(self.colors.buffer_bg)
+            .highlight_spacing(HighlightSpacing::Always)
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(self.colors.footer_border_color))
+                    .title(title)
+                    .title_bottom(Line::from(" (Tab) sort | (R) reverse | (Esc) back ").centered()),
+            );
+        frame.render_stateful_widget(t, area, &mut drilldown.state);
+    }
+
+    fn render_kind_tabs(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        tabs: &[(Option<ArtifactKind>, usize, u64)],
+    ) {
+        let titles = tabs.iter().map(|(kind, count, bytes)| {
+            let label = kind.map_or("All", ArtifactKind::label);
+            format!(
+                " {label} ({}, {}) ",
+                format_count(*count as u64),
+                format_bytes(*bytes)
+            )
+        });
+        let selected = tabs.iter().position(|(kind, ..)| *kind == self.kind_tab);
+        let tabs = Tabs::new(titles)
+            .select(selected)
+            .style(
+                Style::new()
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.header_bg),
+            )
+            .highlight_style(
+                Style::new()
+                    .add_modifier(Modifier::REVERSED)
+                    .fg(self.colors.selected_row_style_fg),
+            )
+            .padding("", "")
+            .divider("│");
+        frame.render_widget(tabs, area);
+    }
+
+    fn render_groups(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(groups) = self.groups.as_mut() else {
+            return;
+        };
+
+        let header_style = Style::default()
+            .fg(self.colors.header_fg)
+            .bg(self.colors.header_bg);
+        let selected_row_style = Style::default()
+            .add_modifier(Modifier::REVERSED)
+            .fg(self.colors.selected_row_style_fg);
+
+        let header = ["Folder", "Directories", "Size ↓"]
+            .into_iter()
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(header_style);
+        let absolute = self.path_display == PathDisplay::Absolute;
+        let rows: Vec<Row> = groups
+            .rows()
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let color = match i % 2 {
+                    0 => self.colors.normal_row_color,
+                    _ => self.colors.alt_row_color,
+                };
+                let style = Style::new().fg(self.colors.row_fg).bg(color);
+                match row {
+                    GroupRow::Header(group) => Row::new([
+                        format!("{} {}", if group.expanded { "▾" } else { "▸" }, group.name),
+                        format_count(group.members.len() as u64),
+                        format_bytes(group.bytes),
+                    ])
+                    .style(style.add_modifier(Modifier::BOLD)),
+                    GroupRow::Member(path, bytes) => Row::new([
+                        format!("    {}", display_path(path, absolute)),
+                        String::new(),
+                        format_bytes(bytes),
+                    ])
+                    .style(style),
+                }
+            })
+            .collect();
+        let title = format!(
+            " {} top-level folders, {} in total ",
+            groups.groups.len(),
+            format_bytes(groups.groups.iter().map(|group| group.bytes).sum())
+        );
+        let bottom_title =
+            Line::from(" (Enter/Space) expand/collapse, show a directory | (Esc) back ").centered();
+        let t = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(12),
+                Constraint::Length(12),
+            ],
+        )
+        .header(header)
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Double)
+                .border_style(Style::new().fg(self.colors.footer_border_color))
+                .title(title)
+                .title_bottom(bottom_title),
+        )
+        .row_highlight_style(selected_row_style)
+        .bg(self.colors.buffer_bg)
+        .highlight_spacing(HighlightSpacing::Always);
+        frame.render_stateful_widget(t, area, &mut groups.state);
+    }
+
+    fn render_duplicates(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(duplicates) = self.duplicates.as_mut() else {
+            return;
+        };
+
+        let header_style = Style::default()
+            .fg(self.colors.header_fg)
+            .bg(self.colors.header_bg);
+        let selected_row_style = Style::default()
+            .add_modifier(Modifier::REVERSED)
+            .fg(self.colors.selected_row_style_fg);
+
+        let bottom_title =
+            Line::from(" (Tab) packages/savings per project | (Esc) back ").centered();
+        let absolute = self.path_display == PathDisplay::Absolute;
+        let t = if duplicates.show_savings {
+            let header = ["Project", "Estimated savings ↓"]
+                .into_iter()
+                .map(Cell::from)
+                .collect::<Row>()
+                .style(header_style);
+            let rows = duplicates.savings.iter().enumerate().map(|(i, (path, bytes))| {
+                let color = match i % 2 {
+                    0 => self.colors.normal_row_color,
+                    _ => self.colors.alt_row_color,
+                };
+                Row::new([
+                    display_path(path, absolute),
+                    format_bytes(*bytes),
+                ])
+                .style(Style::new().fg(self.colors.row_fg).bg(color))
+            });
+            let title = format!(
+                " A shared content-addressable store (pnpm) would save about {} across {} projects ",
+                format_bytes(duplicates.redundant()),
+                duplicates.savings.len()
+            );
+            Table::new(rows, [Constraint::Fill(1), Constraint::Length(20)])
+                .header(header)
+                .block(
+                    Block::bordered()
+                        .border_type(BorderType::Double)
+                        .border_style(Style::new().fg(self.colors.footer_border_color))
+                        .title(title)
+                        .title_bottom(bottom_title),
+                )
+        } else {
+            let header = ["Package", "Version", "Copies", "Each", "Total", "Redundant ↓"]
+                .into_iter()
+                .map(Cell::from)
+                .collect::<Row>()
+                .style(header_style);
+            let rows = duplicates.packages.iter().enumerate().map(|(i, package)| {
+                let color = match i % 2 {
+                    0 => self.colors.normal_row_color,
+                    _ => self.colors.alt_row_color,
+                };
+                Row::new([
+                    package.name.clone(),
+                    package.version.clone(),
+                    package.copies.len().to_string(),
+                    format_bytes(package.largest_copy()),
+                    format_bytes(package.total()),
+                    format_bytes(package.redundant()),
+                ])
+                .style(Style::new().fg(self.colors.row_fg).bg(color))
+            });
+            let title = format!(
+                " {} packages installed in more than one project, {} redundant ",
+                duplicates.packages.len(),
+                format_bytes(duplicates.redundant())
+            );
+            Table::new(
+                rows,
+                [
+                    Constraint::Fill(2),
+                    Constraint::Fill(1),
+                    Constraint::Length(7),
+                    Constraint::Length(11),
+                    Constraint::Length(11),
+                    Constraint::Length(12),
+                ],
+            )
+            .header(header)
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(self.colors.footer_border_color))
+                    .title(title)
+                    .title_bottom(bottom_title),
+            )
+        }
+        .row_highlight_style(selected_row_style)
+        .bg(self.colors.buffer_bg)
+        .highlight_spacing(HighlightSpacing::Always);
+        frame.render_stateful_widget(t, area, &mut duplicates.state);
+    }
+
+    // Runs after the table so its offset already accounts for this frame's height.
+    fn render_scrollbar(&mut self, frame: &mut Frame, area: Rect) {
+        let visible = self.visible_rows(area);
+        let mut scroll_state = scrollbar_state(self.items.len(), visible, self.state.offset());
+        frame.render_stateful_widget(
+            Scrollbar::default()
+                .orientation(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None),
+            area.inner(Margin {
+                vertical: 1,
+                horizontal: 1,
+            }),
+            &mut scroll_state,
+        );
+    }
+
+    fn spinner(&self) -> &'static str {
+        SPINNER[self.ticks % SPINNER.len()]
+    }
+
+    fn render_footer(&self, frame: &mut Frame, area: Rect) {
+        let mut info_text: Vec<String> = match self.jump_query.as_deref() {
+            _ if let Some(deletion) = self.deletion.as_ref() => vec![
+                format!("{} {}", self.spinner(), deletion.status_line()),
+                format!("… queued | {} deleting | ✓ done | ✗ failed", self.spinner()),
+            ],
+            _ if self.command_line.is_some() || self.command_error.is_some() => {
+                let input = self.command_line.as_deref().unwrap_or_default();
+                vec![
+                    format!(":{input}▏"),
+                    self.command_error.clone().unwrap_or_else(|| {
+                        "select size>1GB age>90d | filter work | sort size desc | tag later | note TEXT | root add PATH | preset save NAME | export FILE | import FILE | suggest | delete"
+                            .to_string()
+                    }),
+                ]
+            }
+            Some(query) => {
+                let matches = self
+                    .items
+                    .iter()
+                    .filter(|data| fuzzy_score(query, &data.display_name()).is_some())
+                    .count();
+                vec![
+                    format!("Jump to: {query}▏ ({matches} matches)"),
+                    "(Enter) confirm | (Esc) cancel".to_string(),
+                ]
+            }
+            None if self.narrow => vec![
+                "(Esc) quit | (↑↓) move | (Enter) select | (D) delete | (Tab) sort".to_string(),
+            ],
+            None => vec![
+                "(Esc) quit | (↑↓) move | (←→) color | (Tab) sort menu | (R) reverse | (Ctrl-P) jump | (B) largest | (*) bookmark | (') bookmarks only | (P) details | (U) duplicates | (G) group by folder | ([ ]) type tabs | (O) scan roots | (V) path display | (M) collapse monorepos | (!) shell here | (C) changes since last scan | (F) refresh sizes | (:) command"
+                    .to_string(),
+                match (self.read_only, self.keymap) {
+                    (true, _) => {
+                        "(Enter/Space) select/deselect | (A) select all | read-only, deleting is disabled"
+                    }
+                    (false, Keymap::Npkill) => {
+                        "(Space) delete highlighted | (Enter) select/deselect | (A) select all | (D) delete selected"
+                    }
+                    (false, Keymap::Default) => {
+                        "(Enter/Space) select/deselect | (A) select all | (D) delete selected | (X) delete highlighted | (Enter) packages when details are open"
+                    }
+                }
+                .to_string(),
+            ],
+        };
+
+        if self.narrow {
+            info_text.truncate(1);
+        }
+        let lines = info_text.into_iter().map(Line::from);
+        let full_path = self
+            .state
+            .selected()
+            .and_then(|i| self.items.get(i))
+            .map(|data| {
+                format!(
+                    " {} · {} bytes ",
+                    self.show_path(&data.path),
+                    format_count(data.bytes)
+                )
+            })
+            .unwrap_or_default();
+        let mut block = Block::bordered()
+            .border_type(BorderType::Double)
+            .border_style(Style::new().fg(self.colors.footer_border_color))
+            .title(Line::from(full_path).centered())
+            .title_bottom(Line::from(self.notice.clone().unwrap_or_default()).centered());
+        if !self.last_filter.is_empty() {
+            block = block.title_bottom(
+                Line::from(format!(
+                    " filter: {} ({} matches) ",
+                    self.last_filter,
+                    format_count(self.items.len() as u64)
+                ))
+                .left_aligned(),
+            );
+        }
+        let roots: Vec<String> = self.roots.iter().map(|root| self.show_path(root)).collect();
+        block = block.title(Line::from(format!(" {} ", roots.join(" · "))).right_aligned());
+        if !self.root_scans.is_empty() {
+            let scanning: Vec<String> = self
+                .root_scans
+                .iter()
+                .map(|(root, _)| self.show_path(root))
+                .collect();
+            block = block.title_bottom(
+                Line::from(format!(
+                    " {} Scanning {} ",
+                    self.spinner(),
+                    scanning.join(" · ")
+                ))
+                .left_aligned(),
+            );
+        }
+        if self.read_only {
+            block = block.title(
+                Line::from(" READ-ONLY ").left_aligned().style(
+                    Style::new()
+                        .fg(tailwind::RED.c400)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            );
+        }
+        let info_footer = Paragraph::new(Text::from_iter(lines))
+            .style(
+                Style::new()
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.buffer_bg),
+            )
+            .centered()
+            .block(block);
+
+        frame.render_widget(info_footer, area)
+    }
+
+    fn render_preview(&mut self, frame: &mut Frame, area: Rect) {
+        let absolute = self.path_display == PathDisplay::Absolute;
+        let total = self.total_bytes();
+        let selected_bytes = self.selected_bytes();
+        let mut lines: Vec<Line> = Vec::new();
+        if let Some(data) = self.state.selected().and_then(|i| self.items.get(i)) {
+            let preview = self
+                .previews
+                .entry(data.path.clone())
+                .or_insert_with(|| Preview::load(&data.path, &data.project));
+
+            lines.push(Line::from(display_path(&data.path, absolute)).bold());
+            if let Some(note) = self.notes.get(&data.path) {
+                lines.push(Line::from(format!("Note:     {note}")).fg(tailwind::AMBER.c300));
+            }
+            if let Some(reasons) = self.suggestions.get(&data.path) {
+                lines.push(Line::from(format!("Suggested: {reasons}")).fg(tailwind::SKY.c300));
+            }
+            if data.locked {
+                lines.push(
+                    Line::from(
+                        "Locked:   you can't write to it or its parent, so it can't be deleted",
+                    )
+                    .fg(tailwind::RED.c400),
+                );
+            }
+            lines.push(Line::from(format!(
+                "Size:     {} ({} bytes)",
+                data.size(),
+                format_count(data.bytes)
+            )));
+            lines.push(Line::from(format!(
+                "Files:    {}",
+                format_count(data.file_count)
+            )));
+            if let Some(modified) = data.modified.and_then(|m| m.elapsed().ok()) {
+                let days = modified.as_secs() / 86_400;
+                lines.push(Line::from(format!("Modified: {days} days ago")));
+            }
+            let mut share = format!(
+                "Share:    {} of everything found",
+                format_share(data.bytes, total)
+            );
+            if data.selected {
+                share.push_str(&format!(
+                    ", {} of the selection",
+                    format_share(data.bytes, selected_bytes)
+                ));
+            }
+            lines.push(Line::from(share));
+            if in_cloud_sync(&data.path) {
+                lines.push(
+                    Line::from("Synced:   deleting this makes the sync client upload the change")
+                        .fg(tailwind::AMBER.c300),
+                );
+            }
+
+            if let Some(twins) = twins_of(&self.lockfile_twins, &data.path) {
+                lines.push(Line::from(""));
+                lines.push(Line::from(format!("Same {} as", twins.lockfile)).bold());
+                for (artifact, _) in twins
+                    .artifacts
+                    .iter()
+                    .filter(|(path, _)| *path != data.path)
+                {
+                    let project = artifact.parent().unwrap_or(artifact);
+                    lines.push(Line::from(format!("  {}", display_path(project, absolute))));
+                }
+                lines.push(Line::from(format!(
+                    "Sharing one install (symlink or pnpm store) saves {}",
+                    format_bytes(twins.redundant())
+                )));
+            }
+
+            lines.push(Line::from(""));
+            lines.push(Line::from("Largest packages").bold());
+            for (name, bytes) in &preview.largest_packages {
+                lines.push(Line::from(format!("{:>10}  {name}", format_bytes(*bytes))));
+            }
+
+            let package_lines = preview.package_lines();
+            if !package_lines.is_empty() {
+                lines.push(Line::from(""));
+                lines.push(Line::from("package.json").bold());
+                lines.extend(package_lines.into_iter().map(Line::from));
+            }
+        }
+
+        let preview = Paragraph::new(Text::from(lines))
+            .style(
+                Style::new()
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.buffer_bg),
+            )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(self.colors.footer_border_color))
+                    .title(" Details "),
+            );
+
+        frame.render_widget(preview, area)
+    }
+
+    fn render_deletion_warning(&self, frame: &mut Frame, area: Rect) {
+        let Some(warnings) = self.deletion_warning.as_ref() else {
+            return;
+        };
+
+        let mut lines = Vec::new();
+        if let Some(data) = self
+            .quick_target
+            .as_ref()
+            .and_then(|path| self.items.iter().find(|data| data.path == *path))
+        {
+            lines.push(
+                Line::from(format!(
+                    "Delete {} ({})?",
+                    self.show_path(&data.path),
+                    data.size()
+                ))
+                .bold(),
+            );
+            lines.push(Line::from(""));
+        }
+        if !warnings.is_empty() {
+            lines.push(Line::from("Check these before deleting:").bold());
+            lines.push(Line::from(""));
+        }
+        for (path, reason) in warnings {
+            lines.push(Line::from(path.to_string_lossy().to_string()));
+            lines.push(Line::from(format!("  {reason}")));
+        }
+        lines.push(Line::from(""));
+        let prompt = if self.confirmations_left > 1 {
+            "(Y) delete anyway, then (Y) again | any other key cancels"
+        } else if warnings.is_empty() {
+            "(Y) delete | any other key cancels"
+        } else {
+            "(Y) delete anyway | any other key cancels"
+        };
+        lines.push(Line::from(prompt).centered());
+
+        let [popup] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(popup);
+
+        let warning = Paragraph::new(Text::from(lines))
+            .style(
+                Style::new()
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.buffer_bg),
+            )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(tailwind::RED.c500))
+                    .title(" Confirm deletion "),
+            );
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(warning, popup);
+    }
+
+    fn render_root_menu(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(menu) = self.root_menu.as_mut() else {
+            return;
+        };
+
+        let [popup] = Layout::vertical([Constraint::Length(self.roots.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(popup);
+
+        let absolute = self.path_display == PathDisplay::Absolute;
+        let items = self.roots.iter().map(|root| {
+            let scanning = self.root_scans.iter().any(|(path, _)| path == root);
+            let marker = if scanning { "⟳" } else { " " };
+            ListItem::new(format!(" {marker} {}", display_path(root, absolute)))
+        });
+        let list = List::new(items)
+            .style(
+                Style::new()
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.buffer_bg),
+            )
+            .highlight_style(
+                Style::new()
+                    .add_modifier(Modifier::REVERSED)
+                    .fg(self.colors.selected_row_style_fg),
+            )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(self.colors.footer_border_color))
+                    .title(" Scan roots ")
+                    .title_bottom(Line::from(" (A) add | (D) remove | (Esc) ").centered()),
+            );
+
+        frame.render_widget(Clear, popup);
+        frame.render_stateful_widget(list, popup, menu);
+    }
+
+    fn render_sort_menu(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(menu) = self.sort_menu.as_mut() else {
+            return;
+        };
+
+        let [popup] = Layout::vertical([Constraint::Length(SortField::ALL.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup] = Layout::horizontal([Constraint::Length(34)])
+            .flex(Flex::Center)
+            .areas(popup);
+
+        let arrow = |reversed: bool| if reversed { "↓" } else { "↑" };
+        let items = SortField::ALL.iter().map(|field| {
+            let marker = match self.secondary_sort {
+                _ if *field == self.sorted_by => format!("1{}", arrow(self.sort_reversed)),
+                Some((secondary, reversed)) if *field == secondary => {
+                    format!("2{}", arrow(reversed))
+                }
+                _ => "  ".to_string(),
+            };
+            ListItem::new(format!(" {marker} {}", field.label()))
+        });
+        let list = List::new(items)
+            .style(
+                Style::new()
+                    .fg(self.colors.row_fg)
+                    .bg(self.colors.buffer_bg),
+            )
+            .highlight_style(
+                Style::new()
+                    .add_modifier(Modifier::REVERSED)
+                    .fg(self.colors.selected_row_style_fg),
+            )
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::new().fg(self.colors.footer_border_color))
+                    .title(" Sort by ")
+                    .title_bottom(Line::from(" (Enter/R) 1st | (S) 2nd | (X) ").centered()),
+            );
+
+        frame.render_widget(Clear, popup);
+        frame.render_stateful_widget(list, popup, menu);
+    }
+}
+
+fn network_notice(config: &Config) -> Option<String> {
+    let skipped = skipped_network_mounts(config);
+    if skipped.is_empty() {
+        return None;
+    }
+    let mounts = skipped
+        .iter()
+        .map(|mount| display_path(mount, config.absolute_paths))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!(
+        " Skipped network mounts: {mounts} (--network-mounts to scan them) "
+    ))
+}
+
+fn twins_of<'a>(groups: &'a [LockfileTwins], path: &Path) -> Option<&'a LockfileTwins> {
+    groups
+        .iter()
+        .find(|twins| twins.artifacts.iter().any(|(artifact, _)| artifact == path))
+}
+
+// The first row on screen: kept where it was unless that hides the highlighted
+// row, and never so far down that the end of the list leaves rows empty.
+fn window_offset(offset: usize, selected: Option<usize>, visible: usize, len: usize) -> usize {
+    let visible = visible.max(1);
+    let offset = offset.min(len.saturating_sub(visible));
+    match selected {
+        Some(selected) if selected < offset => selected,
+        Some(selected) if selected >= offset + visible => selected + 1 - visible,
+        _ => offset,
+    }
+}
+
+// Positions are table offsets, the first row on screen, which stop once the last
+// row is visible. So the thumb covers visible/total of the track and only reaches
+// the bottom when the last row shows.
+fn scrollbar_state(total: usize, visible: usize, offset: usize) -> ScrollbarState {
+    let positions = total.saturating_sub(visible) + 1;
+    ScrollbarState::new(positions)
+        .viewport_content_length(visible.max(1))
+        .position(offset)
+}
+
+pub fn compare_with_history(items: &mut [Data], history: &History) {
+    for data in items {
+        data.previous_bytes = history
+            .get(&data.path)
+            .and_then(|samples| samples.last())
+            .map(|(_, bytes)| *bytes);
+    }
+}
+
+pub fn generate_data(config: &Config) -> Result<Vec<Data>, CleanerError> {
+    scan(config).map(|(data, _)| data)
+}
+
+type ScanResult = Result<(Vec<Data>, usize), CleanerError>;
+
+// Also returns how many directories were found but couldn't be sized.
+pub fn scan(config: &Config) -> ScanResult {
+    scan_reporting(config, !config.progress_json)
+}
+
+// For scans while the TUI is up, where a progress line would garble the screen.
+fn scan_silently(config: &Config) -> ScanResult {
+    scan_reporting(config, false)
+}
+
+fn scan_reporting(config: &Config, report: bool) -> ScanResult {
+    let artifacts = get_array(config)?;
+    let progress = Progress::new(artifacts.len());
+    thread::scope(|scope| {
+        if report {
+            scope.spawn(|| progress.report_until_finished());
+        }
+        let mut data = size_data(artifacts, config, &progress);
+        progress.finish();
+        if let Some(top) = config.top {
+            keep_largest(&mut data, top);
+        }
+        Ok((data, progress.failed()))
+    })
+}
+
+fn size_data(artifacts: Vec<PathBuf>, config: &Config, progress: &Progress) -> Vec<Data> {
+    size_pool(config.size_threads, config.root(), config.background).install(|| {
+        artifacts
+            .into_par_iter()
+            .filter_map(|path| {
+                let path = path.canonicalize().unwrap_or(path);
+                let kind = ArtifactKind::detect(&path).unwrap_or_default();
+                if !config.scans(kind) {
+                    return None;
+                }
+                if config.git_only && git::repo_root(kind.project_dir(&path)).is_none() {
+                    return None;
+                }
+                // A directory removed or locked down since discovery is left out.
+                let stats = match artifact_stats(&path, kind) {
+                    Ok(stats) => stats,
+                    Err(error) => {
+                        progress.record_failure();
+                        if config.progress_json {
+                            events::size_failed(&error);
+                        }
+                        return None;
+                    }
+                };
+                progress.record(stats.bytes);
+                if config.progress_json {
+                    events::dir_sized(&path, stats.bytes, stats.files);
+                }
+                if stats.bytes < config.min_size {
+                    return None;
+                }
+                let modified = symlink_metadata(&path).and_then(|m| m.modified()).ok();
+
+                let project = kind.project_dir(&path).to_path_buf();
+                let user = config.all_users.then(|| home_owner(&path)).flatten();
+                Some(Data {
+                    project_type: ProjectType::detect(&project),
+                    orphan: kind == ArtifactKind::NodeModules && is_orphan(&project),
+                    locked: !can_delete(&path),
+                    project,
+                    path,
+                    selected: false,
+                    bytes: stats.bytes,
+                    file_count: stats.files,
+                    cloud_files: stats.cloud_files,
+                    modified,
+                    kind,
+                    nested: Vec::new(),
+                    status: None,
+                    user,
+                    previous_bytes: None,
+                })
+            })
+            .collect()
+    })
+}
+
+fn artifact_stats(path: &Path, kind: ArtifactKind) -> Result<FolderStats, CleanerError> {
+    match kind {
+        ArtifactKind::Pnpm => reclaimable_stats(path),
+        _ => folder_stats(path),
+    }
+    .map_err(|source| CleanerError::Size {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+// A collapsed monorepo row is re-sized from its own directory plus every nested one.
+fn resize(data: &mut Data) {
+    let stats = std::iter::once(&data.path)
+        .chain(&data.nested)
+        .filter_map(|path| {
+            let kind = ArtifactKind::detect(path).unwrap_or_default();
+            artifact_stats(path, kind).ok()
+        })
+        .fold(FolderStats::default(), |total, stats| total + stats);
+    data.bytes = stats.bytes;
+    data.file_count = stats.files;
+    data.cloud_files = stats.cloud_files;
+    data.modified = symlink_metadata(&data.path).and_then(|m| m.modified()).ok();
+}
+
+fn keep_largest(items: &mut Vec<Data>, n: usize) {
+    if n < items.len() {
+        items.select_nth_unstable_by(n, |a, b| b.bytes.cmp(&a.bytes));
+        items.truncate(n);
+    }
+}
+
+fn directed(ordering: std::cmp::Ordering, reversed: bool) -> std::cmp::Ordering {
+    if reversed {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+fn interactive_shell() -> String {
+    let (var, fallback) = if cfg!(windows) {
+        ("COMSPEC", "cmd.exe")
+    } else {
+        ("SHELL", "sh")
+    };
+    env::var(var).unwrap_or_else(|_| fallback.to_string())
+}
+
+fn highlight_matches(text: &str, positions: &[usize]) -> Line<'static> {
+    let matched_style = Style::new()
+        .fg(tailwind::YELLOW.c300)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut line = Line::default();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            let style = if run_matched {
+                matched_style
+            } else {
+                Style::new()
+            };
+            line.push_span(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    let style = if run_matched {
+        matched_style
+    } else {
+        Style::new()
+    };
+    line.push_span(Span::styled(run, style));
+    line
+}
+
+const fn checkbox(selected: bool) -> &'static str {
+    if selected { "  ☑" } else { "  ☐" }
+}
+
+fn constraint_len_calculator(items: &[Data]) -> (u16, u16, u16) {
+    let name_len = items
+        .par_iter()
+        .map(|data| data.display_name().width())
+        .max()
+        .unwrap_or(0);
+    let parent_len = items
+        .par_iter()
+        .map(|data| data.size().width())
+        .max()
+        .unwrap_or(0);
+    let selected_len = if items.is_empty() {
+        0
+    } else {
+        checkbox(false).width()
+    };
+
+    #[allow(clippy::cast_possible_truncation)]
+    (selected_len as u16, name_len as u16, parent_len as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::message::Message;
+    use crate::model::{Data, SortField};
+    use crate::state::View;
+    use ratatui::{
+        Terminal,
+        backend::TestBackend,
+        buffer::Buffer,
+        crossterm::event::{Event, KeyCode, KeyEvent},
+        layout::Rect,
+        widgets::{Scrollbar, StatefulWidget},
+    };
+    use std::{io, path::PathBuf};
+
+    use super::{App, CleanerError, constraint_len_calculator, scrollbar_state, window_offset};
+
+    fn screen(app: &mut App, keys: &[KeyCode]) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        let events = keys.iter().map(|&code| Event::Key(KeyEvent::from(code)));
+        app.run_with_backend(&mut terminal, events).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn headless_app() -> App {
+        let items = vec![
+            Data {
+                path: PathBuf::from("/work/web/node_modules"),
+                project: PathBuf::from("/work/web"),
+                bytes: 300_000_000,
+                ..Default::default()
+            },
+            Data {
+                path: PathBuf::from("/work/api/node_modules"),
+                project: PathBuf::from("/work/api"),
+                bytes: 20_000_000,
+                ..Default::default()
+            },
+        ];
+        let config = Config {
+            root: Some(PathBuf::from("/work")),
+            ..Config::default()
+        };
+        App::with_items(config, items, Default::default())
+    }
+
+    #[test]
+    fn rows_stay_put_while_a_deletion_runs() {
+        let mut app = headless_app();
+        app.demo = true;
+        app.items[0].selected = true;
+        let deleting = app.items[0].path.clone();
+        app.remove_directories();
+        assert!(app.deletion.is_some());
+
+        app.apply_filter("web");
+        app.update(Message::ToggleNested);
+        app.update(Message::SwitchKindTab { forward: true });
+        app.update(Message::ToggleBookmarksFilter);
+        assert_eq!(2, app.items.len());
+        assert!(app.hidden_items.is_empty() && app.expanded_items.is_none());
+
+        while app.deletion.is_some() {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            app.poll_deletion();
+        }
+        assert!(
+            !app.items
+                .iter()
+                .any(|data| data.path == deleting && data.selected)
+        );
+    }
+
+    #[test]
+    fn only_a_root_that_scanned_is_guarded() {
+        let mut app = headless_app();
+        app.root_guard = Some(Vec::new());
+        for (root, result) in [
+            ("/broken", Err(io::Error::other("denied"))),
+            ("/extra", Ok(())),
+        ] {
+            let root = PathBuf::from(root);
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let result = result
+                .map(|_| (Vec::new(), 0))
+                .map_err(|source| CleanerError::Scan {
+                    path: root.clone(),
+                    source,
+                });
+            sender.send(result).unwrap();
+            app.roots.push(root.clone());
+            app.root_scans.push((root, receiver));
+        }
+
+        app.demo = true;
+        app.items[0].selected = true;
+        app.remove_directories();
+        app.poll_root_scans();
+        assert_eq!(2, app.root_scans.len());
+
+        app.deletion = None;
+        app.poll_root_scans();
+        assert_eq!(Some(vec![PathBuf::from("/extra")]), app.root_guard);
+        assert!(!app.roots.contains(&PathBuf::from("/broken")));
+    }
+
+    #[test]
+    fn only_the_visible_window_of_a_large_list_is_rendered() {
+        let items = (0..5000)
+            .map(|i| Data {
+                path: PathBuf::from(format!("/work/p{i}/node_modules")),
+                bytes: i + 1,
+                ..Default::default()
+            })
+            .collect();
+        let mut app = App::with_items(Config::default(), items, Default::default());
+        app.select_row(4999);
+        let table = screen(&mut app, &[]);
+        assert!(table.contains("p4999/node_modules"), "{table}");
+        assert!(!table.contains("p0/node_modules"), "{table}");
+        assert!(app.state.offset() > 4990);
+    }
+
+    #[test]
+    fn exported_selection_imports_back_by_path() {
+        let file = std::env::temp_dir().join("nm-finder-rs-selection-plan.txt");
+        let mut app = headless_app();
+        app.run_command("select size>100MB");
+        app.run_command(&format!("export {}", file.display()));
+        assert_eq!(
+            "/work/web/node_modules\n",
+            std::fs::read_to_string(&file).unwrap()
+        );
+
+        std::fs::write(
+            &file,
+            "# approved\n/work/api/node_modules\n/gone/node_modules\n",
+        )
+        .unwrap();
+        app.run_command(&format!("import {}", file.display()));
+        let selected: Vec<_> = app.selected_paths().into_iter().collect();
+        assert_eq!(vec![PathBuf::from("/work/api/node_modules")], selected);
+        assert!(app.notice.as_deref().unwrap().contains("1 not found"));
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn selection_plans_cover_hidden_and_nested_rows() {
+        let file = std::env::temp_dir().join("nm-finder-rs-selection-hidden.txt");
+        let mut app = headless_app();
+        app.items.push(Data {
+            path: PathBuf::from("/work/web/packages/ui/node_modules"),
+            project: PathBuf::from("/work/web/packages/ui"),
+            bytes: 1_000_000,
+            ..Default::default()
+        });
+        app.run_command("select size<100MB");
+        app.apply_filter("web");
+        app.run_command(&format!("export {}", file.display()));
+        assert_eq!(
+            "/work/api/node_modules\n/work/web/packages/ui/node_modules\n",
+            std::fs::read_to_string(&file).unwrap()
+        );
+
+        app.run_command("deselect all");
+        app.update(Message::ToggleNested);
+        app.run_command(&format!("import {}", file.display()));
+        let notice = app.notice.clone().unwrap();
+        assert!(!notice.contains("not found"), "{notice}");
+        assert!(notice.contains("1 hidden by the filter"), "{notice}");
+        assert!(app.expanded_items.is_none());
+
+        app.request_deletion();
+        let warnings = app.deletion_warning.take().unwrap();
+        assert!(
+            warnings
+                .iter()
+                .any(|(path, _)| path.ends_with("api/node_modules"))
+        );
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn idle_ticks_do_not_need_a_redraw() {
+        let mut app = headless_app();
+        screen(&mut app, &[]);
+        assert!(!app.dirty);
+        app.update(Message::Tick);
+        assert!(!app.dirty);
+        app.update(Message::NextRow);
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn window_follows_the_highlighted_row() {
+        assert_eq!(0, window_offset(0, Some(4), 5, 100));
+        assert_eq!(3, window_offset(0, Some(7), 5, 100));
+        assert_eq!(7, window_offset(20, Some(7), 5, 100));
+        assert_eq!(95, window_offset(99, Some(99), 5, 100));
+        assert_eq!(0, window_offset(3, Some(1), 5, 3));
+    }
+
+    #[test]
+    fn scrollbar_thumb_matches_the_visible_share_of_rows() {
+        let thumb = |total, visible, offset| {
+            let area = Rect::new(0, 0, 1, 10);
+            let mut buffer = Buffer::empty(area);
+            let mut state = scrollbar_state(total, visible, offset);
+            Scrollbar::default()
+                .begin_symbol(None)
+                .end_symbol(None)
+                .render(area, &mut buffer, &mut state);
+            (0..10)
+                .map(|y| {
+                    if buffer[(0, y)].symbol() == "█" {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        };
+        assert_eq!("#####.....", thumb(20, 10, 0));
+        assert_eq!(".....#####", thumb(20, 10, 10));
+        assert_eq!("##########", thumb(4, 10, 0));
+    }
+
+    #[test]
+    fn renders_rows_and_modals_headlessly() {
+        let mut app = headless_app();
+        let table = screen(&mut app, &[]);
+        assert!(table.contains("web/node_modules"), "{table}");
+        assert!(table.contains("api/node_modules"), "{table}");
+        assert!(table.contains("2 listed"), "{table}");
+        assert!(table.contains("0 selected"), "{table}");
+
+        let warning = screen(&mut app, &[KeyCode::Char(' '), KeyCode::Char('d')]);
+        assert!(warning.contains("Confirm deletion"), "{warning}");
+
+        let table = screen(&mut app, &[KeyCode::Esc]);
+        assert!(!table.contains("Confirm deletion"), "{table}");
+        assert!(table.contains("1 selected"), "{table}");
+
+        let prompt = screen(&mut app, &[KeyCode::Char('j'), KeyCode::Char('x')]);
+        assert!(prompt.contains("Delete /work/web/node_modules"), "{prompt}");
+        let table = screen(&mut app, &[KeyCode::Char('n')]);
+        assert!(!table.contains("Confirm deletion"), "{table}");
+        assert!(table.contains("1 selected"), "{table}");
+    }
+
+    #[test]
+    fn a_configured_sort_beats_the_saved_view() {
+        let saved = View {
+            sort_field: SortField::Name,
+            sort_reversed: false,
+            highlighted: Some(PathBuf::from("/work/api/node_modules")),
+        };
+        let mut app = headless_app();
+        app.restore_view(Some(saved.clone()));
+        assert_eq!(SortField::Name, app.sorted_by);
+        assert_eq!(Some(0), app.state.selected());
+
+        let mut app = headless_app();
+        app.config.sort_configured = true;
+        app.restore_view(Some(saved));
+        assert_eq!(SortField::Size, app.sorted_by);
+        assert_eq!(
+            "/work/api/node_modules",
+            app.items[app.state.selected().unwrap()]
+                .path
+                .to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn moving_through_a_filter_with_no_matches_does_nothing() {
+        let mut app = headless_app();
+        app.apply_filter("zzzz-nomatch");
+        assert_eq!(None, app.state.selected());
+        app.next_row();
+        app.previous_row();
+        assert_eq!(None, app.state.selected());
+        screen(&mut app, &[KeyCode::Char('j'), KeyCode::Char('k')]);
+    }
+
+    #[test]
+    fn select_all_leaves_locked_rows_alone() {
+        let mut app = headless_app();
+        app.items[1].locked = true;
+        let table = screen(&mut app, &[KeyCode::Char('a')]);
+        assert!(table.contains("🔒"), "{table}");
+        assert!(app.items.iter().all(|data| data.selected != data.locked));
+    }
 
     #[test]
     fn constraint_len_calculator_works() {
         let test_data = vec![
             Data {
-                name: "Emirhan Tala".to_string(),
-                size: "Cambridgelaan 6XX\n3584 XX Utrecht".to_string(),
-                selected_for_deletion: "true".to_string(),
+                path: PathBuf::from("Emirhan Tala"),
+                bytes: 1,
+                selected: true,
+                ..Default::default()
             },
             Data {
-                name: "thistextis26characterslong".to_string(),
-                size: "this line is 31 characters long\nbottom line is 33 characters long"
-                    .to_string(),
-                selected_for_deletion: "true".to_string(),
+                path: PathBuf::from("thistextis26characterslong"),
+                bytes: 1_288_490_189,
+                selected: true,
+                ..Default::default()
             },
         ];
         let (selected_len, name_len, size_len) = constraint_len_calculator(&test_data);
 
-        assert_eq!(4, selected_len);
+        assert_eq!(3, selected_len);
         assert_eq!(26, name_len);
-        assert_eq!(65, size_len);
+        assert_eq!(7, size_len);
     }
 }
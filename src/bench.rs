@@ -0,0 +1,150 @@
+use bytesize::ByteSize;
+use color_eyre::Result;
+use rayon::prelude::*;
+use std::{
+    env, fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::config::Config;
+use crate::remove::remove_tree;
+use crate::scanner::{discover, folder_stats, size_pool};
+
+struct Run {
+    discovery: Duration,
+    sizing: Duration,
+    dirs_visited: usize,
+    artifacts: usize,
+    bytes: u64,
+}
+
+pub fn run(root: &Path, runs: usize, config: &Config) {
+    let pool = size_pool(config.size_threads, root, config.background);
+    println!("Benchmarking {} ({runs} runs)", root.display());
+
+    let results: Vec<Run> = (1..=runs)
+        .map(|n| {
+            let started = Instant::now();
+            let discovery = discover(root, config);
+            let discovered = started.elapsed();
+
+            let started = Instant::now();
+            let bytes: u64 = pool.install(|| {
+                discovery
+                    .artifacts
+                    .par_iter()
+                    .filter_map(|path| folder_stats(path).ok())
+                    .map(|stats| stats.bytes)
+                    .sum()
+            });
+            let sizing = started.elapsed();
+
+            let run = Run {
+                discovery: discovered,
+                sizing,
+                dirs_visited: discovery.dirs_visited,
+                artifacts: discovery.artifacts.len(),
+                bytes,
+            };
+            println!(
+                "run {n}: discovery {:.2?} ({:.0} dirs/s), sizing {:.2?}, {} artifacts, {}",
+                run.discovery,
+                per_second(run.dirs_visited, run.discovery),
+                run.sizing,
+                run.artifacts,
+                ByteSize::b(run.bytes)
+            );
+            run
+        })
+        .collect();
+
+    let total = |f: fn(&Run) -> Duration| results.iter().map(f).sum::<Duration>();
+    let discovery = total(|r| r.discovery) / runs as u32;
+    let sizing = total(|r| r.sizing) / runs as u32;
+    let fastest = results
+        .iter()
+        .map(|r| r.discovery + r.sizing)
+        .min()
+        .unwrap_or_default();
+    let dirs = results.iter().map(|r| r.dirs_visited).sum::<usize>() / runs;
+
+    println!();
+    println!(
+        "mean discovery: {discovery:.2?} ({:.0} dirs/s)",
+        per_second(dirs, discovery)
+    );
+    println!("mean sizing:    {sizing:.2?}");
+    println!("fastest run:    {fastest:.2?}");
+    match peak_memory() {
+        Some(peak) => println!("peak memory:    {}", ByteSize::b(peak)),
+        None => println!("peak memory:    unavailable on this platform"),
+    }
+}
+
+// Compares std's sequential remove_dir_all with remove_tree on the same generated
+// tree: 300 packages of 10 directories with 20 small files each. The tree goes
+// under `root` so the filesystem being measured can be picked.
+pub fn run_delete(root: Option<&Path>, runs: usize) -> Result<()> {
+    let root = root
+        .map_or_else(env::temp_dir, Path::to_path_buf)
+        .join("nm-finder-rs-bench-delete");
+    println!("Benchmarking deletion in {} ({runs} runs)", root.display());
+
+    let mut sequential = Duration::ZERO;
+    let mut parallel = Duration::ZERO;
+    for n in 1..=runs {
+        let files = generate_tree(&root)?;
+        let started = Instant::now();
+        fs::remove_dir_all(&root)?;
+        let std_time = started.elapsed();
+
+        generate_tree(&root)?;
+        let started = Instant::now();
+        remove_tree(&root)?;
+        let tree_time = started.elapsed();
+
+        println!(
+            "run {n}: remove_dir_all {std_time:.2?} ({:.0} files/s), remove_tree {tree_time:.2?} ({:.0} files/s)",
+            per_second(files, std_time),
+            per_second(files, tree_time)
+        );
+        sequential += std_time;
+        parallel += tree_time;
+    }
+
+    println!();
+    println!("mean remove_dir_all: {:.2?}", sequential / runs as u32);
+    println!("mean remove_tree:    {:.2?}", parallel / runs as u32);
+    println!(
+        "speedup:             {:.2}x",
+        sequential.as_secs_f64() / parallel.as_secs_f64().max(f64::EPSILON)
+    );
+    Ok(())
+}
+
+fn generate_tree(root: &Path) -> io::Result<usize> {
+    let mut files = 0;
+    for package in 0..300 {
+        for dir in 0..10 {
+            let dir = root.join(format!("node_modules/pkg-{package}/lib-{dir}"));
+            fs::create_dir_all(&dir)?;
+            for file in 0..20 {
+                fs::write(dir.join(format!("{file}.js")), "module.exports = {};\n")?;
+                files += 1;
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn per_second(count: usize, elapsed: Duration) -> f64 {
+    count as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+}
+
+fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
@@ -0,0 +1,204 @@
+use color_eyre::{Result, eyre::eyre};
+use std::{
+    io::{self, Write},
+    time::Instant,
+};
+
+use crate::app::{compare_with_history, generate_data};
+use crate::config::Config;
+use crate::events;
+use crate::format::{format_bytes, format_count};
+use crate::guard::{can_delete, outside_roots, running_as_root};
+use crate::hook;
+use crate::model::{Data, display_path};
+use crate::mounts::snapshot_mounts;
+use crate::notify::{self, Summary, hostname};
+use crate::palette::{Criteria, filter_matches};
+use crate::remove::{self, throughput};
+use crate::scanner::{deletion_roots, scan_roots, skipped_network_mounts};
+use crate::space::{self, SpaceCheck};
+use crate::state::{Preset, load_bookmarks, load_history, load_presets, load_tags, log_deletions};
+
+pub fn find_preset(name: &str) -> Result<Preset> {
+    load_presets()
+        .into_iter()
+        .find(|preset| preset.name == name)
+        .ok_or_else(|| eyre!("no preset named `{name}`"))
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Outcome {
+    pub deleted: usize,
+    pub bytes_freed: u64,
+    pub failures: usize,
+    // Matched but not deletable by this user, see `guard::can_delete`.
+    pub skipped: usize,
+}
+
+// Applies a preset saved from the TUI. Without `delete` nothing is removed.
+pub fn run(preset_name: &str, delete: bool, config: &Config) -> Result<()> {
+    for mount in skipped_network_mounts(config) {
+        eprintln!(
+            "skipping network mount {}",
+            display_path(&mount, config.absolute_paths)
+        );
+    }
+    let preset = find_preset(preset_name)?;
+    let outcome = apply(&preset, delete, false, config, generate_data(config)?)?;
+    if outcome.failures > 0 {
+        return Err(eyre!(
+            "{} directories could not be deleted",
+            outcome.failures
+        ));
+    }
+    Ok(())
+}
+
+// The preset applied to an existing scan, so the daemon can reuse the one it
+// reports metrics for. Running as root, deleting needs `confirmed_root` or someone
+// typing the confirmation on stdin; the daemon has nobody there, so it passes
+// --allow-root along instead.
+pub fn apply(
+    preset: &Preset,
+    delete: bool,
+    confirmed_root: bool,
+    config: &Config,
+    data: Vec<Data>,
+) -> Result<Outcome> {
+    let criteria = Criteria::parse(&preset.criteria).map_err(|message| eyre!(message))?;
+    let tags = load_tags();
+    let bookmarks = load_bookmarks();
+    let window = config.safety_window();
+
+    let show = |path: &std::path::Path| display_path(path, config.absolute_paths);
+    let mut data = data;
+    compare_with_history(&mut data, &load_history());
+    let matches: Vec<Data> = data
+        .into_iter()
+        .filter(|data| {
+            let tags = tags.get(&data.path);
+            !data.is_recent(window)
+                && filter_matches(&preset.filter, data, tags, bookmarks.contains(&data.path))
+                && criteria.matches(data, tags)
+        })
+        .collect();
+    let (matches, locked): (Vec<Data>, Vec<Data>) =
+        matches.into_iter().partition(|data| can_delete(&data.path));
+    for data in &locked {
+        eprintln!("skipping {}, you can't delete it", show(&data.path));
+    }
+
+    let total: u64 = matches.iter().map(|data| data.bytes).sum();
+    for data in &matches {
+        println!("{:>10}  {}", data.size(), show(&data.path));
+    }
+    println!(
+        "{} directories, {} in total",
+        format_count(matches.len() as u64),
+        format_bytes(total)
+    );
+    if !delete {
+        println!("Dry run, pass --yes to delete them");
+        return Ok(Outcome {
+            skipped: locked.len(),
+            ..Outcome::default()
+        });
+    }
+    if config.read_only {
+        return Err(eyre!("read-only mode, nothing was deleted"));
+    }
+    let roots = deletion_roots(&scan_roots(config), config);
+    if running_as_root()
+        && let Some(path) =
+            outside_roots(matches.iter().map(|data| data.path.as_path()), &roots).first()
+    {
+        return Err(eyre!(
+            "refusing to delete {} outside the scanned roots while running as root",
+            path.display()
+        ));
+    }
+    if running_as_root() && !confirmed_root {
+        // --yes alone isn't enough for root; someone has to type the confirmation.
+        print!("Running as root, type `delete` to remove these directories: ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if answer.trim() != "delete" {
+            return Err(eyre!("not confirmed, nothing was deleted"));
+        }
+    }
+
+    for (mount, fs_type) in snapshot_mounts(matches.iter().map(|data| data.path.as_path())) {
+        eprintln!(
+            "note: {} is on {fs_type} with snapshots, space is freed once they are removed",
+            show(&mount)
+        );
+    }
+    if let Some(pre) = &config.hooks.pre_batch {
+        let output =
+            hook::run_batch(pre).map_err(|error| eyre!("pre-batch hook failed: {error}"))?;
+        if !output.is_empty() {
+            println!("{output}");
+        }
+    }
+    let space = SpaceCheck::before(matches.iter().filter_map(|data| data.path.parent()));
+    let started = Instant::now();
+    let mut failures = 0;
+    let mut files = 0;
+    let mut deleted = Vec::new();
+    for data in &matches {
+        let result = remove::delete_entry(&data.path, &data.nested, &config.hooks, &roots);
+        if config.progress_json {
+            events::delete_done(&data.path, data.bytes, &result);
+        }
+        match result {
+            Ok(output) => {
+                if !output.is_empty() {
+                    println!("{output}");
+                }
+                files += data.file_count;
+                deleted.push((data.path.clone(), data.bytes));
+            }
+            Err(error) => {
+                eprintln!("{error}");
+                failures += 1;
+            }
+        }
+    }
+    if let Some(post) = &config.hooks.post_batch {
+        match hook::run_batch(post) {
+            Ok(output) if !output.is_empty() => println!("{output}"),
+            Ok(_) => {}
+            Err(error) => eprintln!("post-batch hook failed: {error}"),
+        }
+    }
+    log_deletions(&deleted)?;
+    let expected = deleted.iter().map(|(_, bytes)| bytes).sum();
+    println!(
+        "Deleted {} directories: {} files in {:.1?} ({})",
+        format_count(deleted.len() as u64),
+        format_count(files),
+        started.elapsed(),
+        throughput(files, expected, started.elapsed())
+    );
+    println!("{}", space::summary(expected, space.reclaimed()));
+    if let Some(url) = &config.webhook_url {
+        let summary = Summary {
+            host: &hostname(),
+            preset: &preset.name,
+            deleted: deleted.len(),
+            bytes_freed: expected,
+            failures,
+        };
+        // The cleanup already happened; a notification that doesn't go out isn't fatal.
+        if let Err(error) = notify::post(url, &summary.to_json()) {
+            eprintln!("could not notify {url}: {error}");
+        }
+    }
+    Ok(Outcome {
+        deleted: deleted.len(),
+        bytes_freed: expected,
+        failures,
+        skipped: locked.len(),
+    })
+}
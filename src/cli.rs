@@ -0,0 +1,396 @@
+use color_eyre::{Result, eyre::eyre};
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::model::SortField;
+
+pub const USAGE: &str = "\
+Usage: node-module-finder-rs [COMMAND] [OPTIONS]
+
+Commands:
+  (none)                 Open the interactive cleaner
+  list [OPTIONS]         Print the discovered directories as a table and exit
+  bench [OPTIONS]        Time the discovery and sizing phases
+  clean --preset <NAME>  List (or with --yes, delete) directories matching a saved preset
+  daemon [OPTIONS]       Rescan periodically and serve Prometheus metrics
+  self-update            Replace this binary with the latest release, after checking its SHA-256
+                         (published with the release, so it catches corruption, not tampering)
+
+List options:
+  --sort <FIELD>[:asc|desc]
+                         name, size, age, files, depth or parent (default: size:desc)
+  --json                 Print a JSON document (with schema_version) instead of a table
+  --bytes                Print allocated bytes (du -B1) instead of rounded sizes
+
+Bench options:
+  --root <PATH>          Directory to scan (default: home directory)
+  --runs <N>             Number of runs (default: 3)
+  --delete               Time deleting a generated node_modules tree instead,
+                         created under --root (default: temp directory)
+
+Clean options:
+  --preset <NAME>        Preset saved in the TUI with `:preset save <NAME>`
+  --yes                  Delete the matches instead of only listing them
+
+Daemon options:
+  --listen <ADDR>        Address serving /metrics (default: 127.0.0.1:9464)
+  --interval <SECS>      Seconds between scans (default: 3600)
+  --preset <NAME>        Also delete this preset's matches after every scan
+
+Options:
+  --scan-threads <N>     Threads used to walk directories
+  --size-threads <N>     Threads used to size discovered directories (default: all
+                         cores, or 2 when the root is on a spinning disk)
+  --include-hidden       Also scan dot-directories and the usual system skips
+  --exclude <GLOB>       Don't scan directories matching GLOB, e.g. '*.bak' or '~/work/legacy';
+                         repeatable, and added to the config's `exclude`
+  --all-users            Scan every readable home under /home or /Users (needs admin rights)
+  --all-mounts           Also scan mounted user volumes (/media/$USER/*, /Volumes/*)
+  --network-mounts       Scan NFS/SMB/sshfs mounts instead of skipping them
+  --cloud-sync           Scan Dropbox/OneDrive/Google Drive folders instead of skipping them
+  --read-only            Browse without being able to delete anything
+  --background           Scan at idle CPU and I/O priority so builds aren't slowed down
+  --top <N>              Only keep the N largest directories
+  --git-only             Only keep projects inside a git repository, which a fresh clone
+                         and install restores
+  --absolute-paths       Print full paths instead of abbreviating the home directory to ~
+  --progress-json        Report progress as JSON lines on stderr (scan_started,
+                         dir_found, dir_sized, delete_done)
+  --allow-root           Allow running as root (deletions then need a second confirmation,
+                         except in the daemon, which has no one to ask)
+  --demo                 Open the cleaner on generated projects; deletions are only simulated
+  -h, --help             Print this help
+
+Environment:
+  NMC_CONFIG             Config file to read instead of the default
+  NMC_<KEY>              Overrides a config key, e.g. NMC_ROOT=/srv or NMC_MIN_SIZE=100MB
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Tui,
+    List {
+        sort: Option<(SortField, bool)>,
+        json: bool,
+        bytes: bool,
+    },
+    Bench {
+        root: Option<PathBuf>,
+        runs: usize,
+        delete: bool,
+    },
+    Clean {
+        preset: String,
+        yes: bool,
+    },
+    Daemon {
+        listen: String,
+        interval: u64,
+        preset: Option<String>,
+    },
+    SelfUpdate,
+    Help,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub scan_threads: Option<usize>,
+    pub size_threads: Option<usize>,
+    pub include_hidden: bool,
+    pub all_mounts: bool,
+    pub scan_network: bool,
+    pub scan_cloud_sync: bool,
+    pub read_only: bool,
+    pub top: Option<usize>,
+    pub progress_json: bool,
+    pub absolute_paths: bool,
+    pub all_users: bool,
+    pub background: bool,
+    pub git_only: bool,
+    pub exclude: Vec<String>,
+}
+
+impl Overrides {
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(threads) = self.scan_threads {
+            config.scan_threads = Some(threads);
+        }
+        if let Some(threads) = self.size_threads {
+            config.size_threads = Some(threads);
+        }
+        if self.include_hidden {
+            config.include_hidden = true;
+        }
+        if self.all_mounts {
+            config.all_mounts = true;
+        }
+        if self.scan_network {
+            config.scan_network = true;
+        }
+        if self.scan_cloud_sync {
+            config.scan_cloud_sync = true;
+        }
+        if self.read_only {
+            config.read_only = true;
+        }
+        if let Some(top) = self.top {
+            config.top = Some(top);
+        }
+        if self.progress_json {
+            config.progress_json = true;
+        }
+        if self.absolute_paths {
+            config.absolute_paths = true;
+        }
+        if self.all_users {
+            config.all_users = true;
+        }
+        if self.background {
+            config.background = true;
+        }
+        if self.git_only {
+            config.git_only = true;
+        }
+        config.exclude.extend(self.exclude.iter().cloned());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub command: Command,
+    pub overrides: Overrides,
+    pub allow_root: bool,
+    pub demo: bool,
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Cli> {
+    let mut args = args.into_iter().peekable();
+    let mut command = match args.peek().map(String::as_str) {
+        Some("bench") => {
+            args.next();
+            Command::Bench {
+                root: None,
+                runs: 3,
+                delete: false,
+            }
+        }
+        Some("list") => {
+            args.next();
+            Command::List {
+                sort: None,
+                json: false,
+                bytes: false,
+            }
+        }
+        Some("clean") => {
+            args.next();
+            Command::Clean {
+                preset: String::new(),
+                yes: false,
+            }
+        }
+        Some("daemon") => {
+            args.next();
+            Command::Daemon {
+                listen: String::from("127.0.0.1:9464"),
+                interval: 3600,
+                preset: None,
+            }
+        }
+        Some("self-update") => {
+            args.next();
+            Command::SelfUpdate
+        }
+        _ => Command::Tui,
+    };
+    let mut overrides = Overrides::default();
+    let mut allow_root = false;
+    let mut demo = false;
+
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), &mut command) {
+            ("-h" | "--help", _) => command = Command::Help,
+            ("--scan-threads", _) => overrides.scan_threads = Some(threads(&mut args, &arg)?),
+            ("--size-threads", _) => overrides.size_threads = Some(threads(&mut args, &arg)?),
+            ("--include-hidden", _) => overrides.include_hidden = true,
+            ("--all-mounts", _) => overrides.all_mounts = true,
+            ("--network-mounts", _) => overrides.scan_network = true,
+            ("--cloud-sync", _) => overrides.scan_cloud_sync = true,
+            ("--read-only", _) => overrides.read_only = true,
+            ("--top", _) => overrides.top = Some(threads(&mut args, &arg)?),
+            ("--progress-json", _) => overrides.progress_json = true,
+            ("--absolute-paths", _) => overrides.absolute_paths = true,
+            ("--all-users", _) => overrides.all_users = true,
+            ("--background", _) => overrides.background = true,
+            ("--git-only", _) => overrides.git_only = true,
+            ("--exclude", _) => overrides.exclude.push(value(&mut args, &arg)?),
+            ("--allow-root", _) => allow_root = true,
+            ("--demo", Command::Tui) => demo = true,
+            ("--json", Command::List { json, .. }) => *json = true,
+            ("--bytes", Command::List { bytes, .. }) => *bytes = true,
+            ("--sort", Command::List { sort, .. }) => {
+                let value = value(&mut args, &arg)?;
+                *sort = Some(sort_order(&value).ok_or_else(|| {
+                    eyre!("--sort expects name, size, age, files, depth or parent, optionally with :asc or :desc")
+                })?);
+            }
+            ("--root", Command::Bench { root, .. }) => {
+                *root = Some(PathBuf::from(value(&mut args, &arg)?));
+            }
+            ("--runs", Command::Bench { runs, .. }) => *runs = threads(&mut args, &arg)?,
+            ("--delete", Command::Bench { delete, .. }) => *delete = true,
+            ("--preset", Command::Clean { preset, .. }) => *preset = value(&mut args, &arg)?,
+            ("--yes", Command::Clean { yes, .. }) => *yes = true,
+            ("--listen", Command::Daemon { listen, .. }) => *listen = value(&mut args, &arg)?,
+            ("--interval", Command::Daemon { interval, .. }) => {
+                *interval = threads(&mut args, &arg)? as u64;
+            }
+            ("--preset", Command::Daemon { preset, .. }) => {
+                *preset = Some(value(&mut args, &arg)?);
+            }
+            (other, _) => return Err(eyre!("unknown argument `{other}`\n\n{USAGE}")),
+        }
+    }
+
+    if let Command::Clean { preset, .. } = &command
+        && preset.is_empty()
+    {
+        return Err(eyre!("clean requires --preset <NAME>"));
+    }
+
+    Ok(Cli {
+        command,
+        overrides,
+        allow_root,
+        demo,
+    })
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    args.next().ok_or_else(|| eyre!("{flag} expects a value"))
+}
+
+// "size", "size:desc" or "age:asc".
+fn sort_order(value: &str) -> Option<(SortField, bool)> {
+    let (field, direction) = value.split_once(':').unwrap_or((value, "asc"));
+    let reversed = match direction {
+        "asc" => false,
+        "desc" => true,
+        _ => return None,
+    };
+    SortField::from_order(field, reversed)
+}
+
+fn threads(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<usize> {
+    value(args, flag)?
+        .parse()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| eyre!("{flag} expects a positive number"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, parse};
+    use crate::config::Config;
+    use crate::model::SortField;
+    use std::path::PathBuf;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parse_defaults_to_the_tui() {
+        assert_eq!(Command::Tui, parse(args("")).unwrap().command);
+        assert_eq!(
+            Command::SelfUpdate,
+            parse(args("self-update")).unwrap().command
+        );
+    }
+
+    #[test]
+    fn parse_reads_bench_options() {
+        assert_eq!(
+            Command::Bench {
+                root: Some(PathBuf::from("/tmp")),
+                runs: 5,
+                delete: false,
+            },
+            parse(args("bench --root /tmp --runs 5")).unwrap().command
+        );
+        assert!(parse(args("bench --runs many")).is_err());
+        assert!(parse(args("bench --root")).is_err());
+        assert!(parse(args("--root /tmp")).is_err());
+    }
+
+    #[test]
+    fn parse_reads_list_sort_and_top() {
+        let cli = parse(args("list --sort age:desc --top 20")).unwrap();
+        assert_eq!(
+            Command::List {
+                sort: Some((SortField::Modified, false)),
+                json: false,
+                bytes: false,
+            },
+            cli.command
+        );
+        assert_eq!(Some(20), cli.overrides.top);
+        assert!(parse(args("--top 0")).is_err());
+        match parse(args("list --sort modified:desc")).unwrap().command {
+            Command::List { sort, .. } => assert_eq!(Some((SortField::Modified, true)), sort),
+            command => panic!("expected list, got {command:?}"),
+        }
+        assert!(parse(args("list --sort colour")).is_err());
+        assert!(parse(args("list --sort size:up")).is_err());
+        assert!(parse(args("--sort size")).is_err());
+    }
+
+    #[test]
+    fn exclude_adds_to_the_config_patterns() {
+        let cli = parse(args("--exclude *.bak --exclude ~/work/legacy")).unwrap();
+        let mut config = Config::parse("exclude = vendor");
+        cli.overrides.apply(&mut config);
+        assert_eq!(vec!["vendor", "*.bak", "~/work/legacy"], config.exclude);
+        assert!(parse(args("--exclude")).is_err());
+    }
+
+    #[test]
+    fn parse_requires_a_preset_for_clean() {
+        assert_eq!(
+            Command::Clean {
+                preset: "big-and-stale".to_string(),
+                yes: true
+            },
+            parse(args("clean --yes --preset big-and-stale"))
+                .unwrap()
+                .command
+        );
+        assert!(parse(args("clean --yes")).is_err());
+    }
+
+    #[test]
+    fn parse_reads_daemon_options() {
+        assert_eq!(
+            Command::Daemon {
+                listen: "0.0.0.0:9100".to_string(),
+                interval: 600,
+                preset: Some("weekly".to_string()),
+            },
+            parse(args(
+                "daemon --listen 0.0.0.0:9100 --interval 600 --preset weekly"
+            ))
+            .unwrap()
+            .command
+        );
+        assert!(parse(args("daemon --interval 0")).is_err());
+    }
+
+    #[test]
+    fn parse_reads_thread_overrides_for_any_command() {
+        let cli = parse(args("bench --scan-threads 2 --size-threads 8")).unwrap();
+        assert_eq!(Some(2), cli.overrides.scan_threads);
+        assert_eq!(Some(8), cli.overrides.size_threads);
+        assert!(parse(args("--scan-threads 0")).is_err());
+    }
+}
@@ -0,0 +1,420 @@
+use bytesize::ByteSize;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::dirs::config_dir;
+use crate::format::NumberFormat;
+use crate::hook::Hooks;
+use crate::model::{ArtifactKind, ColumnWidths, Heatmap, SortField, home_dir, palette_index};
+use crate::suggest::SuggestRule;
+
+const CONFIG_FILE: &str = "config";
+const DAY: u64 = 86_400;
+const ENV_PREFIX: &str = "NMC_";
+const KEYS: [&str; 31] = [
+    "safety_window_days",
+    "scan_threads",
+    "size_threads",
+    "sort",
+    "palette",
+    "density",
+    "heatmap",
+    "column_widths",
+    "root",
+    "min_size",
+    "warn_size",
+    "critical_size",
+    "suggest",
+    "include_hidden",
+    "skip_dirs",
+    "exclude",
+    "kinds",
+    "all_mounts",
+    "scan_network",
+    "scan_cloud_sync",
+    "read_only",
+    "git_only",
+    "keymap",
+    "number_format",
+    "delete_command",
+    "pre_delete_hook",
+    "post_delete_hook",
+    "pre_batch_hook",
+    "post_batch_hook",
+    "webhook_url",
+    "background",
+];
+
+// Skipped during discovery unless `include_hidden` is set or `skip_dirs` replaces them.
+const DEFAULT_SKIP_DIRS: [&str; 10] = [
+    ".cache", ".vscode", ".local", ".npm", ".nvm", ".steam", ".var", ".cargo", "caches", "Caches",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Keymap {
+    #[default]
+    Default,
+    // Space deletes the highlighted row, like npkill.
+    Npkill,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub safety_window_days: u64,
+    pub scan_threads: Option<usize>,
+    pub size_threads: Option<usize>,
+    pub sort_field: SortField,
+    pub sort_reversed: bool,
+    // Whether `sort` was set at all, in which case it beats the saved view.
+    pub sort_configured: bool,
+    pub palette: usize,
+    pub compact_rows: bool,
+    pub heatmap: Option<Heatmap>,
+    pub column_widths: ColumnWidths,
+    pub root: Option<PathBuf>,
+    pub min_size: u64,
+    pub warn_size: Option<u64>,
+    pub critical_size: Option<u64>,
+    pub suggest: SuggestRule,
+    pub include_hidden: bool,
+    pub skip_dirs: Option<Vec<String>>,
+    // Glob patterns, see `glob::Glob`; `--exclude` adds to them.
+    pub exclude: Vec<String>,
+    // None scans for every kind.
+    pub kinds: Option<Vec<ArtifactKind>>,
+    pub all_mounts: bool,
+    pub scan_network: bool,
+    pub scan_cloud_sync: bool,
+    pub read_only: bool,
+    pub git_only: bool,
+    pub top: Option<usize>,
+    pub progress_json: bool,
+    pub keymap: Keymap,
+    pub absolute_paths: bool,
+    pub all_users: bool,
+    pub number_format: Option<NumberFormat>,
+    pub hooks: Hooks,
+    pub webhook_url: Option<String>,
+    pub background: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            safety_window_days: 7,
+            scan_threads: None,
+            size_threads: None,
+            sort_field: SortField::Size,
+            sort_reversed: false,
+            sort_configured: false,
+            palette: 0,
+            compact_rows: false,
+            heatmap: Some(Heatmap::default()),
+            column_widths: ColumnWidths::default(),
+            root: None,
+            min_size: 0,
+            warn_size: None,
+            critical_size: None,
+            suggest: SuggestRule::default(),
+            include_hidden: false,
+            skip_dirs: None,
+            exclude: Vec::new(),
+            kinds: None,
+            all_mounts: false,
+            scan_network: false,
+            scan_cloud_sync: false,
+            read_only: false,
+            git_only: false,
+            top: None,
+            progress_json: false,
+            keymap: Keymap::Default,
+            absolute_paths: false,
+            all_users: false,
+            number_format: None,
+            hooks: Hooks::default(),
+            webhook_url: None,
+            background: false,
+        }
+    }
+}
+
+impl Config {
+    // Environment variables (NMC_ROOT, NMC_MIN_SIZE, ...) win over the file, and
+    // NMC_CONFIG points at a different file altogether.
+    pub fn load() -> Self {
+        let path = env::var_os("NMC_CONFIG")
+            .map(PathBuf::from)
+            .unwrap_or_else(config_path);
+        let mut config = fs::read_to_string(path)
+            .map(|contents| Self::parse(&contents))
+            .unwrap_or_default();
+        config.apply_env(|name| env::var(name).ok());
+        config
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut config = Self::default();
+        for (key, value) in entries(contents) {
+            config.set(key, value);
+        }
+        config
+    }
+
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) {
+        for key in KEYS {
+            if let Some(value) = var(&format!("{ENV_PREFIX}{}", key.to_uppercase())) {
+                self.set(key, value.trim());
+            }
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "safety_window_days" => {
+                if let Ok(days) = value.parse() {
+                    self.safety_window_days = days;
+                }
+            }
+            "scan_threads" => self.scan_threads = value.parse().ok().filter(|n| *n > 0),
+            "size_threads" => self.size_threads = value.parse().ok().filter(|n| *n > 0),
+            // e.g. "size desc"
+            "sort" => {
+                let (field, direction) = value.split_once(' ').unwrap_or((value, "asc"));
+                if let Some((field, reversed)) =
+                    SortField::from_order(field, direction.trim() == "desc")
+                {
+                    self.sort_field = field;
+                    self.sort_reversed = reversed;
+                    self.sort_configured = true;
+                }
+            }
+            "palette" => {
+                if let Some(index) = value.parse().ok().or_else(|| palette_index(value)) {
+                    self.palette = index;
+                }
+            }
+            "density" => self.compact_rows = value == "compact",
+            // "off", or the percentages of the largest entry that are yellow and red.
+            "heatmap" if value == "off" => self.heatmap = None,
+            "heatmap" => {
+                if let Some(heatmap) = Heatmap::parse(value) {
+                    self.heatmap = Some(heatmap);
+                }
+            }
+            // e.g. "name 60%, size 12"; unlisted columns stay on auto.
+            "column_widths" => {
+                if let Some(widths) = ColumnWidths::parse(value) {
+                    self.column_widths = widths;
+                }
+            }
+            "root" => self.root = Some(PathBuf::from(value)).filter(|p| p.is_absolute()),
+            "min_size" => {
+                if let Ok(size) = ByteSize::from_str(value) {
+                    self.min_size = size.as_u64();
+                }
+            }
+            // Rows at or above these stand out whatever the sort order.
+            "warn_size" => self.warn_size = ByteSize::from_str(value).ok().map(|s| s.as_u64()),
+            "critical_size" => {
+                self.critical_size = ByteSize::from_str(value).ok().map(|s| s.as_u64())
+            }
+            // e.g. "1GB*30d, orphans, clean_git", what `:suggest` selects.
+            "suggest" => {
+                if let Some(rule) = SuggestRule::parse(value) {
+                    self.suggest = rule;
+                }
+            }
+            "include_hidden" => self.include_hidden = value == "true",
+            "all_mounts" => self.all_mounts = value == "true",
+            "scan_network" => self.scan_network = value == "true",
+            // Dropbox, OneDrive and Google Drive folders are skipped unless this is set.
+            "scan_cloud_sync" => self.scan_cloud_sync = value == "true",
+            "read_only" => self.read_only = value == "true",
+            "git_only" => self.git_only = value == "true",
+            "background" => self.background = value == "true",
+            // A sample such as "1.234,5"; unset follows LC_NUMERIC.
+            "number_format" => self.number_format = NumberFormat::parse(value),
+            // Runs instead of the built-in removal, e.g. "trash-put {path}".
+            "delete_command" => self.hooks.delete = command(value),
+            "pre_delete_hook" => self.hooks.pre_delete = command(value),
+            "post_delete_hook" => self.hooks.post_delete = command(value),
+            "pre_batch_hook" => self.hooks.pre_batch = command(value),
+            "post_batch_hook" => self.hooks.post_batch = command(value),
+            // Receives a JSON summary after `clean --yes`, e.g. a Slack incoming webhook.
+            "webhook_url" => {
+                self.webhook_url = Some(value.to_string())
+                    .filter(|url| url.starts_with("https://") || url.starts_with("http://"))
+            }
+            "keymap" => {
+                self.keymap = match value {
+                    "npkill" => Keymap::Npkill,
+                    _ => Keymap::Default,
+                }
+            }
+            "skip_dirs" => {
+                self.skip_dirs = Some(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|dir| !dir.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                );
+            }
+            "exclude" => {
+                self.exclude = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|pattern| !pattern.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            // e.g. "node_modules, pnpm"; see `ArtifactKind::name`.
+            "kinds" => {
+                self.kinds = Some(
+                    value
+                        .split(',')
+                        .filter_map(|name| ArtifactKind::from_name(name.trim()))
+                        .collect::<Vec<_>>(),
+                )
+                .filter(|kinds| !kinds.is_empty());
+            }
+            _ => {}
+        }
+    }
+
+    pub fn scans(&self, kind: ArtifactKind) -> bool {
+        self.kinds
+            .as_ref()
+            .is_none_or(|kinds| kinds.contains(&kind))
+    }
+
+    pub fn skip_dirs(&self) -> Vec<String> {
+        match &self.skip_dirs {
+            Some(dirs) => dirs.clone(),
+            None if self.include_hidden => Vec::new(),
+            None => DEFAULT_SKIP_DIRS.map(str::to_string).into(),
+        }
+    }
+
+    // Like `skip_dirs`, but only matched directly inside a home directory: macOS
+    // keeps app data in ~/Library, while a project's own Library/ is still scanned.
+    pub fn home_skip_dirs(&self) -> Vec<String> {
+        if cfg!(target_os = "macos") && self.skip_dirs.is_none() && !self.include_hidden {
+            vec!["Library".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        self.root.as_deref().unwrap_or_else(|| home_dir())
+    }
+
+    pub const fn safety_window(&self) -> Duration {
+        Duration::from_secs(self.safety_window_days * DAY)
+    }
+}
+
+pub fn config_path() -> PathBuf {
+    config_dir().join(CONFIG_FILE)
+}
+
+fn entries(contents: &str) -> impl Iterator<Item = (&str, &str)> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim().trim_matches('"')))
+}
+
+fn command(value: &str) -> Option<String> {
+    Some(value.to_string()).filter(|command| !command.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Keymap};
+    use crate::model::SortField;
+
+    #[test]
+    fn parse_reads_known_keys_and_ignores_the_rest() {
+        let config = Config::parse(
+            "# comment\nsafety_window_days = 14\nscan_threads = 4\nunknown = yes\nnot a pair\n",
+        );
+        assert_eq!(14, config.safety_window_days);
+        assert_eq!(Some(4), config.scan_threads);
+        assert_eq!(None, config.size_threads);
+    }
+
+    #[test]
+    fn parse_keeps_defaults_for_invalid_values() {
+        let config = Config::parse("safety_window_days = soon\nsort = colour");
+        assert_eq!(7, config.safety_window_days);
+        assert_eq!(SortField::Size, config.sort_field);
+        assert!(!config.sort_configured);
+    }
+
+    #[test]
+    fn apply_env_overrides_file_values() {
+        let mut config = Config::parse("min_size = 10MB\nroot = /srv");
+        config.apply_env(|name| match name {
+            "NMC_MIN_SIZE" => Some("1GB".to_string()),
+            "NMC_SORT" => Some("name desc".to_string()),
+            _ => None,
+        });
+        assert_eq!(1_000_000_000, config.min_size);
+        assert_eq!(SortField::Name, config.sort_field);
+        assert!(config.sort_configured);
+        assert_eq!(std::path::Path::new("/srv"), config.root());
+    }
+
+    #[test]
+    fn size_thresholds_parse_like_min_size() {
+        let config = Config::parse("warn_size = 500MB\ncritical_size = 2 GiB");
+        assert_eq!(Some(500_000_000), config.warn_size);
+        assert_eq!(Some(2 * 1024 * 1024 * 1024), config.critical_size);
+        assert_eq!(None, Config::parse("warn_size = big").warn_size);
+    }
+
+    #[test]
+    fn skip_dirs_defaults_can_be_dropped_or_replaced() {
+        assert!(Config::default().skip_dirs().contains(&".npm".to_string()));
+        // ~/Library is only skipped right inside a home, never by name anywhere.
+        assert!(
+            !Config::default()
+                .skip_dirs()
+                .contains(&"Library".to_string())
+        );
+        assert_eq!(
+            cfg!(target_os = "macos"),
+            !Config::default().home_skip_dirs().is_empty()
+        );
+        assert!(
+            Config::parse("include_hidden = true")
+                .skip_dirs()
+                .is_empty()
+        );
+        assert_eq!(
+            vec!["vendor".to_string(), "tmp".to_string()],
+            Config::parse("include_hidden = true\nskip_dirs = vendor, tmp").skip_dirs()
+        );
+    }
+
+    #[test]
+    fn parse_reads_display_preferences() {
+        let config =
+            Config::parse("sort = modified desc\npalette = 2\ndensity = compact\nkeymap = npkill");
+        assert_eq!(SortField::Modified, config.sort_field);
+        assert!(config.sort_reversed);
+        assert_eq!(2, config.palette);
+        assert!(config.compact_rows);
+        assert_eq!(Keymap::Npkill, config.keymap);
+        assert_eq!(5, Config::parse("palette = High-Contrast").palette);
+    }
+}
@@ -0,0 +1,248 @@
+use color_eyre::Result;
+use std::{
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::app::scan;
+use crate::clean;
+use crate::config::Config;
+use crate::state::Preset;
+
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    pub artifact_bytes: u64,
+    pub artifact_directories: usize,
+    pub scan_seconds: f64,
+    pub freed_bytes: u64,
+    pub deleted_directories: u64,
+    pub errors: u64,
+    pub scans: u64,
+}
+
+impl Metrics {
+    // Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let metrics = [
+            (
+                "nmc_artifact_bytes",
+                "gauge",
+                "Bytes in artifact directories found by the last scan.",
+                self.artifact_bytes as f64,
+            ),
+            (
+                "nmc_artifact_directories",
+                "gauge",
+                "Artifact directories found by the last scan.",
+                self.artifact_directories as f64,
+            ),
+            (
+                "nmc_scan_duration_seconds",
+                "gauge",
+                "How long the last scan took.",
+                self.scan_seconds,
+            ),
+            (
+                "nmc_freed_bytes_total",
+                "counter",
+                "Bytes deleted by the daemon's preset cleanups.",
+                self.freed_bytes as f64,
+            ),
+            (
+                "nmc_deleted_directories_total",
+                "counter",
+                "Directories deleted by the daemon's preset cleanups.",
+                self.deleted_directories as f64,
+            ),
+            (
+                "nmc_errors_total",
+                "counter",
+                "Roots that couldn't be scanned, directories that couldn't be sized or deleted.",
+                self.errors as f64,
+            ),
+            (
+                "nmc_scans_total",
+                "counter",
+                "Scans completed since the daemon started.",
+                self.scans as f64,
+            ),
+        ];
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(
+                out,
+                "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}"
+            );
+        }
+        out
+    }
+}
+
+// Rescans every `interval` and serves the latest numbers on /metrics. With a
+// preset, each scan is followed by deleting its matches like `clean --yes`;
+// `confirmed_root` stands in for the confirmation root would type there.
+pub fn run(
+    listen: &str,
+    interval: Duration,
+    preset: Option<&str>,
+    confirmed_root: bool,
+    config: &Config,
+) -> Result<()> {
+    let listener = TcpListener::bind(listen)?;
+    eprintln!(
+        "serving metrics on http://{}/metrics",
+        listener.local_addr()?
+    );
+    let metrics = Arc::new(Mutex::new(Metrics::default()));
+    let served = Arc::clone(&metrics);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let body = lock(&served).render();
+            let _ = respond(stream, &body);
+        }
+    });
+
+    loop {
+        // Looked up every time, so a preset saved in the TUI since takes effect.
+        let found = preset.map(clean::find_preset).transpose();
+        if let Err(error) = &found {
+            eprintln!("{error}");
+            lock(&metrics).errors += 1;
+        }
+        cycle(
+            found.ok().flatten().as_ref(),
+            confirmed_root,
+            config,
+            &metrics,
+        );
+        thread::sleep(interval);
+    }
+}
+
+// One scan and, with a preset, its cleanup. Nothing here reads stdin.
+fn cycle(preset: Option<&Preset>, confirmed_root: bool, config: &Config, metrics: &Mutex<Metrics>) {
+    let started = Instant::now();
+    let result = scan(config);
+    let mut data = None;
+    {
+        let mut metrics = lock(metrics);
+        metrics.scans += 1;
+        metrics.scan_seconds = started.elapsed().as_secs_f64();
+        match result {
+            Ok((scanned, failed)) => {
+                metrics.artifact_bytes = scanned.iter().map(|data| data.bytes).sum();
+                metrics.artifact_directories = scanned.len();
+                metrics.errors += failed as u64;
+                data = Some(scanned);
+            }
+            Err(error) => {
+                eprintln!("{error}");
+                metrics.errors += 1;
+            }
+        }
+    }
+    if let (Some(preset), Some(data)) = (preset, data) {
+        match clean::apply(preset, true, confirmed_root, config, data) {
+            Ok(outcome) => {
+                let mut metrics = lock(metrics);
+                metrics.freed_bytes += outcome.bytes_freed;
+                metrics.deleted_directories += outcome.deleted as u64;
+                metrics.errors += outcome.failures as u64;
+            }
+            Err(error) => {
+                eprintln!("{error}");
+                lock(metrics).errors += 1;
+            }
+        }
+    }
+}
+
+fn lock(metrics: &Mutex<Metrics>) -> std::sync::MutexGuard<'_, Metrics> {
+    metrics.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Scrapes are served one at a time, so a client that connects and sends nothing
+// must not hold up the next one for long.
+fn respond(mut stream: TcpStream, metrics: &str) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let (status, content_type, body) = if request.starts_with("GET /metrics ") {
+        ("200 OK", "text/plain; version=0.0.4", metrics)
+    } else {
+        ("404 Not Found", "text/plain", "not found\n")
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metrics, cycle, lock};
+    use crate::config::Config;
+    use crate::dirs::{state_dir, use_test_base};
+    use crate::state::Preset;
+    use std::fs;
+    use std::sync::Mutex;
+
+    // As root the cleanup used to wait for `delete` on stdin, which a daemon never
+    // gets; with --allow-root passed along it deletes without asking.
+    #[test]
+    fn cycle_cleans_up_without_asking() {
+        let dir = std::env::temp_dir().join("nm-finder-rs-daemon");
+        let _ = fs::remove_dir_all(&dir);
+        use_test_base(&dir.join("home"));
+        let root = dir.join("code");
+        fs::create_dir_all(root.join("app/node_modules/pkg")).unwrap();
+        fs::write(root.join("app/package.json"), "{}").unwrap();
+        let config = Config {
+            root: Some(root.clone()),
+            safety_window_days: 0,
+            ..Config::default()
+        };
+        let preset = Preset {
+            name: "everything".to_string(),
+            filter: String::new(),
+            criteria: "all".to_string(),
+        };
+        let metrics = Mutex::new(Metrics::default());
+
+        cycle(Some(&preset), true, &config, &metrics);
+        let metrics = lock(&metrics);
+        assert_eq!(1, metrics.scans);
+        assert_eq!(1, metrics.artifact_directories);
+        assert_eq!(1, metrics.deleted_directories);
+        assert_eq!(0, metrics.errors);
+        assert!(!root.join("app/node_modules").exists());
+        assert!(state_dir().starts_with(&dir));
+        assert!(state_dir().join("deletions.log").is_file());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn render_writes_help_type_and_value_for_each_metric() {
+        let metrics = Metrics {
+            artifact_bytes: 2048,
+            freed_bytes: 512,
+            ..Metrics::default()
+        };
+        let text = metrics.render();
+        assert!(text.contains(
+            "# HELP nmc_artifact_bytes Bytes in artifact directories found by the last scan.\n\
+             # TYPE nmc_artifact_bytes gauge\n\
+             nmc_artifact_bytes 2048\n"
+        ));
+        assert!(text.contains("# TYPE nmc_freed_bytes_total counter\nnmc_freed_bytes_total 512\n"));
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use crate::model::{ArtifactKind, Data};
+use crate::project::ProjectType;
+use crate::state::History;
+
+// A home directory that doesn't exist, so nothing in the demo can touch real files.
+pub const ROOT: &str = "/home/demo";
+
+const FOLDERS: [&str; 4] = ["code", "work", "experiments", "Downloads"];
+const NAMES: [&str; 12] = [
+    "storefront",
+    "design-system",
+    "api-gateway",
+    "mobile-app",
+    "blog",
+    "dashboard",
+    "cli-tools",
+    "chat-widget",
+    "landing-page",
+    "desktop-client",
+    "docs-site",
+    "payments",
+];
+const TYPES: [ProjectType; 5] = [
+    ProjectType::NextJs,
+    ProjectType::Vite,
+    ProjectType::ReactNative,
+    ProjectType::Electron,
+    ProjectType::Node,
+];
+const DAY: u64 = 24 * 60 * 60;
+
+// The same seed every time, so screenshots and recordings come out identical.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+pub fn projects() -> Vec<Data> {
+    let mut rng = Lcg(0x006e_6d63_6465_6d6f);
+    let now = SystemTime::now();
+    let mut items = Vec::new();
+    for (i, name) in NAMES.iter().enumerate() {
+        let project = PathBuf::from(ROOT)
+            .join(FOLDERS[i % FOLDERS.len()])
+            .join(name);
+        let bytes = (20 + rng.below(1_200)) * 1_000_000;
+        let previous_bytes = (i % 3 != 0).then(|| bytes - rng.below(bytes / 4));
+        items.push(Data {
+            path: project.join("node_modules"),
+            bytes,
+            file_count: bytes / 9_000,
+            modified: Some(now - Duration::from_secs(rng.below(400) * DAY)),
+            project_type: TYPES[i % TYPES.len()],
+            previous_bytes,
+            // Left behind in Downloads, so the orphan styling shows up too.
+            orphan: i % FOLDERS.len() == 3,
+            project: project.clone(),
+            ..Default::default()
+        });
+        // A few projects also carry a Yarn cache next to their node_modules.
+        if i % 4 == 1 {
+            let bytes = (5 + rng.below(300)) * 1_000_000;
+            items.push(Data {
+                path: project.join(".yarn/cache"),
+                bytes,
+                file_count: bytes / 400_000,
+                modified: Some(now - Duration::from_secs(rng.below(400) * DAY)),
+                kind: ArtifactKind::YarnCache,
+                project_type: TYPES[i % TYPES.len()],
+                previous_bytes: Some(bytes),
+                project,
+                ..Default::default()
+            });
+        }
+    }
+    items
+}
+
+// A few weekly samples per row so the trend column has something to draw.
+pub fn history(items: &[Data]) -> History {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut rng = Lcg(items.len() as u64);
+    items
+        .iter()
+        .map(|data| {
+            let samples = (0..6u64)
+                .map(|week| {
+                    let shrink = rng.below(data.bytes / 8 + 1) * (5 - week.min(5)) / 5;
+                    (now - (6 - week) * 7 * DAY, data.bytes - shrink)
+                })
+                .collect();
+            (data.path.clone(), samples)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ROOT, projects};
+
+    #[test]
+    fn generates_the_same_projects_every_time() {
+        let first = projects();
+        let second = projects();
+        assert_eq!(15, first.len());
+        assert!(first.iter().all(|data| data.path.starts_with(ROOT)));
+        let sizes = |items: &[crate::model::Data]| -> Vec<u64> {
+            items.iter().map(|data| data.bytes).collect()
+        };
+        assert_eq!(sizes(&first), sizes(&second));
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(test)]
+use std::cell::RefCell;
+
+use crate::model::home_dir;
+
+const APP_DIR: &str = "nm-finder-rs";
+
+const APP_SUPPORT: &str = "Library/Application Support";
+
+pub fn config_dir() -> PathBuf {
+    base_dir("XDG_CONFIG_HOME", ".config", APP_SUPPORT, "APPDATA").join(APP_DIR)
+}
+
+pub fn state_dir() -> PathBuf {
+    base_dir(
+        "XDG_STATE_HOME",
+        ".local/state",
+        APP_SUPPORT,
+        "LOCALAPPDATA",
+    )
+    .join(APP_DIR)
+}
+
+// For data that can be rebuilt, like the scan history, so it isn't backed up or
+// synced along with the config and state. Windows has no separate cache location,
+// so it gets a subdirectory of the state directory, as `directories` does.
+pub fn cache_dir() -> PathBuf {
+    let dir = base_dir("XDG_CACHE_HOME", ".cache", "Library/Caches", "LOCALAPPDATA").join(APP_DIR);
+    if cfg!(windows) {
+        dir.join("cache")
+    } else {
+        dir
+    }
+}
+
+// Creates the directory (and its parents) the first time something is written there.
+pub fn ensure_dir(dir: &Path) -> io::Result<&Path> {
+    fs::create_dir_all(dir)?;
+    Ok(dir)
+}
+
+#[cfg(test)]
+thread_local! {
+    static TEST_BASE: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+// Points this thread's config, state and cache directories below `dir`, so a test
+// that goes through code saving state doesn't touch the real ones.
+#[cfg(test)]
+pub fn use_test_base(dir: &Path) {
+    TEST_BASE.set(Some(dir.to_path_buf()));
+}
+
+// An absolute XDG variable always wins; otherwise use the platform's usual location.
+fn base_dir(xdg_var: &str, unix_default: &str, macos_default: &str, windows_var: &str) -> PathBuf {
+    #[cfg(test)]
+    if let Some(dir) = TEST_BASE.with_borrow(Clone::clone) {
+        return dir.join(unix_default);
+    }
+    if let Some(dir) = env::var_os(xdg_var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+    {
+        return dir;
+    }
+    let home = home_dir();
+    if cfg!(target_os = "macos") {
+        home.join(macos_default)
+    } else if cfg!(windows) {
+        env::var_os(windows_var)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join("AppData/Local"))
+    } else {
+        home.join(unix_default)
+    }
+}
@@ -0,0 +1,71 @@
+use ratatui::widgets::TableState;
+use std::path::Path;
+
+use crate::preview::package_sizes;
+
+pub struct Drilldown {
+    pub title: String,
+    pub packages: Vec<(String, u64)>,
+    pub state: TableState,
+    pub sort_by_name: bool,
+    pub sort_reversed: bool,
+}
+
+impl Drilldown {
+    pub fn load(title: String, node_modules: &Path) -> Self {
+        Self {
+            title,
+            packages: package_sizes(node_modules),
+            state: TableState::default().with_selected(0),
+            sort_by_name: false,
+            sort_reversed: false,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.packages.iter().map(|(_, bytes)| bytes).sum()
+    }
+
+    pub fn next_row(&mut self) {
+        if self.packages.is_empty() {
+            return;
+        }
+        let i = self
+            .state
+            .selected()
+            .map_or(0, |i| (i + 1) % self.packages.len());
+        self.state.select(Some(i));
+    }
+
+    pub fn previous_row(&mut self) {
+        if self.packages.is_empty() {
+            return;
+        }
+        let count = self.packages.len();
+        let i = self.state.selected().map_or(0, |i| (i + count - 1) % count);
+        self.state.select(Some(i));
+    }
+
+    pub fn toggle_sort_field(&mut self) {
+        self.sort_by_name = !self.sort_by_name;
+        self.sort_reversed = false;
+        self.sort();
+    }
+
+    pub fn reverse(&mut self) {
+        self.sort_reversed = !self.sort_reversed;
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        if self.sort_by_name {
+            self.packages.sort_by(|a, b| a.0.cmp(&b.0));
+        } else {
+            self.packages
+                .sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+        }
+        if self.sort_reversed {
+            self.packages.reverse();
+        }
+    }
+}
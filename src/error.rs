@@ -0,0 +1,31 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+// Failures with the path they happened on, so callers can tell a root that
+// couldn't be read from a directory that couldn't be sized or removed.
+#[derive(Debug)]
+pub enum CleanerError {
+    Scan { path: PathBuf, source: io::Error },
+    Size { path: PathBuf, source: io::Error },
+    Delete { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for CleanerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (action, path, source) = match self {
+            CleanerError::Scan { path, source } => ("scan", path, source),
+            CleanerError::Size { path, source } => ("size", path, source),
+            CleanerError::Delete { path, source } => ("delete", path, source),
+        };
+        write!(f, "could not {action} {}: {source}", path.display())
+    }
+}
+
+impl Error for CleanerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CleanerError::Scan { source, .. }
+            | CleanerError::Size { source, .. }
+            | CleanerError::Delete { source, .. } => Some(source),
+        }
+    }
+}
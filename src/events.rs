@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::CleanerError;
+use crate::json::{Value, object};
+
+// Shared by every JSON document and event this tool prints. Bumped whenever a
+// field is renamed, removed or changes meaning; new fields and events may appear
+// within a version, so consumers should ignore anything they don't recognise.
+pub const SCHEMA_VERSION: u64 = 1;
+
+pub fn schema_version() -> (&'static str, Value) {
+    ("schema_version", Value::Number(SCHEMA_VERSION as f64))
+}
+
+// Line-delimited JSON on stderr (`--progress-json`) for wrapper scripts and CI
+// dashboards. Every line is an object with "schema_version" and "event" fields.
+fn emit(event: &str, fields: impl IntoIterator<Item = (&'static str, Value)>) {
+    let line = object(fields.into_iter().chain([
+        schema_version(),
+        ("event", Value::String(event.to_string())),
+    ]));
+    eprintln!("{line}");
+}
+
+pub fn path(path: &Path) -> Value {
+    Value::String(path.to_string_lossy().into_owned())
+}
+
+pub fn scan_started(roots: &[PathBuf]) {
+    emit(
+        "scan_started",
+        [(
+            "roots",
+            Value::Array(roots.iter().map(|root| path(root)).collect()),
+        )],
+    );
+}
+
+pub fn dir_found(found: &Path) {
+    emit("dir_found", [("path", path(found))]);
+}
+
+pub fn size_failed(error: &CleanerError) {
+    emit("size_failed", [("error", Value::String(error.to_string()))]);
+}
+
+pub fn dir_sized(sized: &Path, bytes: u64, files: u64) {
+    emit(
+        "dir_sized",
+        [
+            ("path", path(sized)),
+            ("bytes", Value::Number(bytes as f64)),
+            ("files", Value::Number(files as f64)),
+        ],
+    );
+}
+
+pub fn delete_done(deleted: &Path, bytes: u64, result: &Result<String, CleanerError>) {
+    let (output, error) = match result {
+        Ok(output) => (Value::String(output.clone()), Value::Null),
+        Err(error) => (Value::Null, Value::String(error.to_string())),
+    };
+    emit(
+        "delete_done",
+        [
+            ("path", path(deleted)),
+            ("bytes", Value::Number(bytes as f64)),
+            ("ok", Value::Bool(result.is_ok())),
+            ("output", output),
+            ("error", error),
+        ],
+    );
+}
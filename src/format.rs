@@ -0,0 +1,305 @@
+use bytesize::ByteSize;
+use std::{env, sync::OnceLock, time::Duration};
+use unicode_width::UnicodeWidthStr;
+
+const ELLIPSIS: &str = "…";
+const DAY: u64 = 86_400;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+static NUMBER_FORMAT: OnceLock<NumberFormat> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub group: Option<char>,
+    pub decimal: char,
+}
+
+impl NumberFormat {
+    // What the output looked like before locales were considered, and what tests see.
+    pub const PLAIN: Self = Self {
+        group: None,
+        decimal: '.',
+    };
+
+    // From a sample such as "1,234.5", "1.234,5", "1 234,5" or "1234.5".
+    pub fn parse(sample: &str) -> Option<Self> {
+        let marks: Vec<char> = sample.chars().filter(|c| !c.is_ascii_digit()).collect();
+        match marks[..] {
+            [decimal] => Some(Self {
+                group: None,
+                decimal,
+            }),
+            [group, decimal] if group != decimal => Some(Self {
+                group: Some(group),
+                decimal,
+            }),
+            _ => None,
+        }
+    }
+
+    // LC_ALL, then LC_NUMERIC, then LANG, like the C library resolves LC_NUMERIC.
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Self::for_locale(&locale)
+    }
+
+    fn for_locale(locale: &str) -> Self {
+        let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+        let (group, decimal) = match language {
+            "" | "C" | "POSIX" => return Self::PLAIN,
+            "de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" => ('.', ','),
+            "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg"
+            | "et" | "lt" | "lv" => (' ', ','),
+            _ => (',', '.'),
+        };
+        Self {
+            group: Some(group),
+            decimal,
+        }
+    }
+}
+
+pub fn set_number_format(format: NumberFormat) {
+    let _ = NUMBER_FORMAT.set(format);
+}
+
+fn number_format() -> NumberFormat {
+    NUMBER_FORMAT.get().copied().unwrap_or(NumberFormat::PLAIN)
+}
+
+pub fn format_count(n: u64) -> String {
+    group_digits(&n.to_string(), number_format())
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    format_size(ByteSize::b(bytes), false)
+}
+
+// bytesize only ever prints "1.2 GiB" style values, so only the decimal point changes.
+pub fn format_size(size: ByteSize, short: bool) -> String {
+    let text = if short {
+        size.display().iec_short().to_string()
+    } else {
+        size.to_string()
+    };
+    let format = number_format();
+    text.replace('.', &format.decimal.to_string())
+}
+
+fn group_digits(digits: &str, format: NumberFormat) -> String {
+    let Some(group) = format.group else {
+        return digits.to_string();
+    };
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(group);
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+pub fn truncate_middle(path: &str, max_width: usize) -> String {
+    if path.width() <= max_width {
+        return path.to_string();
+    }
+
+    let components: Vec<&str> = path.split('/').collect();
+    let mut head = 0;
+    let mut tail = components.len();
+    let fits = |head: usize, tail: usize| {
+        let mut parts = components[..head].to_vec();
+        parts.push(ELLIPSIS);
+        parts.extend_from_slice(&components[tail..]);
+        parts.join("/").width() <= max_width
+    };
+
+    while tail > head + 1 && fits(head, tail - 1) {
+        tail -= 1;
+        if head + 1 < tail && fits(head + 1, tail) {
+            head += 1;
+        }
+    }
+
+    if tail < components.len() {
+        let mut parts = components[..head].to_vec();
+        parts.push(ELLIPSIS);
+        parts.extend_from_slice(&components[tail..]);
+        return parts.join("/");
+    }
+
+    // Not even the last component fits, so keep as much of its end as possible.
+    let mut truncated: String = path
+        .chars()
+        .rev()
+        .scan(ELLIPSIS.width(), |width, c| {
+            *width += c.to_string().width();
+            (*width <= max_width).then_some(c)
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    truncated.insert_str(0, ELLIPSIS);
+    truncated
+}
+
+// Compact age for table columns: "today", "12d", "5mo", "2y".
+pub fn format_age(age: Duration) -> String {
+    match age.as_secs() / DAY {
+        0 => "today".to_string(),
+        days @ 1..60 => format!("{days}d"),
+        days @ 60..730 => format!("{}mo", days / 30),
+        days => format!("{}y", days / 365),
+    }
+}
+
+// Change since the previous scan for the Δ column: "+12 MiB", "-3.0 KiB", "±0" or "new".
+pub fn format_delta(bytes: u64, previous: Option<u64>) -> String {
+    match previous {
+        None => "new".to_string(),
+        Some(previous) if previous == bytes => "±0".to_string(),
+        Some(previous) if previous < bytes => format!("+{}", format_bytes(bytes - previous)),
+        Some(previous) => format!("-{}", format_bytes(previous - bytes)),
+    }
+}
+
+// "12.3%", with "<0.1%" rather than a misleading "0.0%" for tiny non-empty parts.
+pub fn format_share(part: u64, total: u64) -> String {
+    if total == 0 {
+        return String::new();
+    }
+    let percent = part as f64 / total as f64 * 100.0;
+    if part > 0 && percent < 0.05 {
+        "<0.1%".to_string()
+    } else {
+        format!("{percent:.1}%")
+    }
+}
+
+// One bar per sample, scaled between the smallest and largest. A single sample has
+// no trend yet, so it renders as nothing.
+pub fn sparkline(values: &[u64]) -> String {
+    if values.len() < 2 {
+        return String::new();
+    }
+    let min = values.iter().copied().min().unwrap_or(0);
+    let max = values.iter().copied().max().unwrap_or(0);
+    let range = (max - min).max(1) as f64;
+    values
+        .iter()
+        .map(|value| {
+            let level = ((value - min) as f64 / range * (SPARKS.len() - 1) as f64).round();
+            SPARKS[level as usize]
+        })
+        .collect()
+}
+
+// A bar `width` cells long for the largest entry, in eighths of a cell like ncdu.
+pub fn size_bar(bytes: u64, largest: u64, width: usize) -> String {
+    if largest == 0 {
+        return String::new();
+    }
+    let eighths =
+        (bytes.min(largest) as f64 / largest as f64 * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(partial) = (eighths % 8).checked_sub(1) {
+        bar.push(EIGHTHS[partial]);
+    }
+    bar
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        NumberFormat, format_age, format_delta, format_share, group_digits, size_bar, sparkline,
+        truncate_middle,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn truncate_middle_keeps_short_paths() {
+        assert_eq!("/code/app", truncate_middle("/code/app", 20));
+    }
+
+    #[test]
+    fn truncate_middle_keeps_project_and_root() {
+        let path = "~/code/clients/acme/webapp/node_modules";
+        let truncated = truncate_middle(path, 30);
+        assert_eq!("~/code/…/webapp/node_modules", truncated);
+    }
+
+    #[test]
+    fn format_age_switches_units() {
+        let days = |n: u64| Duration::from_secs(n * 86_400);
+        assert_eq!("today", format_age(days(0)));
+        assert_eq!("59d", format_age(days(59)));
+        assert_eq!("6mo", format_age(days(180)));
+        assert_eq!("3y", format_age(days(1100)));
+    }
+
+    #[test]
+    fn number_format_follows_the_locale_or_a_sample() {
+        let german = NumberFormat::for_locale("de_DE.UTF-8");
+        assert_eq!("1.234.567", group_digits("1234567", german));
+        assert_eq!(',', german.decimal);
+        assert_eq!(
+            "1 234 567",
+            group_digits("1234567", NumberFormat::for_locale("fr_FR"))
+        );
+        assert_eq!(
+            "123",
+            group_digits("123", NumberFormat::for_locale("en_US"))
+        );
+        assert_eq!(NumberFormat::PLAIN, NumberFormat::for_locale("C.UTF-8"));
+        assert_eq!(
+            Some(NumberFormat::for_locale("en_GB")),
+            NumberFormat::parse("1,234.5")
+        );
+        assert_eq!(None, NumberFormat::parse("1,234,5"));
+    }
+
+    #[test]
+    fn format_delta_is_signed() {
+        assert_eq!("new", format_delta(10, None));
+        assert_eq!("±0", format_delta(10, Some(10)));
+        assert_eq!("+2.0 KiB", format_delta(3072, Some(1024)));
+        assert_eq!("-1.0 KiB", format_delta(1024, Some(2048)));
+    }
+
+    #[test]
+    fn format_share_keeps_tiny_parts_visible() {
+        assert_eq!("25.0%", format_share(1, 4));
+        assert_eq!("100.0%", format_share(4, 4));
+        assert_eq!("<0.1%", format_share(1, 10_000));
+        assert_eq!("0.0%", format_share(0, 10));
+        assert_eq!("", format_share(0, 0));
+    }
+
+    #[test]
+    fn sparkline_scales_between_min_and_max() {
+        assert_eq!("", sparkline(&[100]));
+        assert_eq!("▁▁", sparkline(&[100, 100]));
+        assert_eq!("▁▅█", sparkline(&[100, 150, 200]));
+    }
+
+    #[test]
+    fn size_bar_is_proportional_to_the_largest() {
+        assert_eq!("████████", size_bar(100, 100, 8));
+        assert_eq!("████", size_bar(50, 100, 8));
+        assert_eq!("█▌", size_bar(3, 16, 8));
+        assert_eq!("", size_bar(0, 100, 8));
+        assert_eq!("", size_bar(0, 0, 8));
+    }
+
+    #[test]
+    fn truncate_middle_cuts_a_single_long_component() {
+        assert_eq!("…_modules", truncate_middle("/node_modules", 9));
+    }
+}
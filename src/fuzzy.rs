@@ -0,0 +1,80 @@
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let positions = fuzzy_positions(query, candidate)?;
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for &i in &positions {
+        score += 1;
+        if previous_match.is_some_and(|p| p + 1 == i) {
+            score += 5;
+        }
+        if i == 0 || matches!(candidate[i - 1], '/' | '-' | '_' | '.' | ' ') {
+            score += 3;
+        }
+        previous_match = Some(i);
+    }
+
+    // Prefer matches that sit close to the end of the path, where the project name lives.
+    let tail = candidate.len() - previous_match.unwrap_or_default();
+    Some(score * 100 - tail as i64)
+}
+
+// Char indices of the first in-order match of every query character, for highlighting.
+pub fn fuzzy_positions(query: &str, candidate: &str) -> Option<Vec<usize>> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let mut positions = Vec::with_capacity(query.len());
+    for (i, c) in candidate.to_lowercase().chars().enumerate() {
+        if positions.len() == query.len() {
+            break;
+        }
+        if c == query[positions.len()] {
+            positions.push(i);
+        }
+    }
+    (positions.len() == query.len()).then_some(positions)
+}
+
+// Char indices covered by the first case-insensitive occurrence of `needle`.
+pub fn substring_positions(needle: &str, haystack: &str) -> Vec<usize> {
+    let needle: Vec<char> = needle.to_lowercase().chars().collect();
+    let haystack: Vec<char> = haystack.to_lowercase().chars().collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle.as_slice())
+        .map(|start| (start..start + needle.len()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_positions, fuzzy_score, substring_positions};
+
+    #[test]
+    fn fuzzy_score_requires_all_characters_in_order() {
+        assert!(fuzzy_score("wbp", "/code/webapp/node_modules").is_some());
+        assert!(fuzzy_score("pbw", "/code/webapp/node_modules").is_none());
+        assert_eq!(Some(0), fuzzy_score("", "/anything"));
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_contiguous_matches() {
+        let contiguous = fuzzy_score("webapp", "/code/webapp/node_modules").unwrap();
+        let scattered = fuzzy_score("webapp", "/work/experiments/bad/app/node_modules").unwrap();
+        assert!(contiguous > scattered);
+    }
+
+    #[test]
+    fn positions_for_highlighting() {
+        assert_eq!(Some(vec![1, 3, 5]), fuzzy_positions("WBp", "/webapp"));
+        assert_eq!(None, fuzzy_positions("x", "/webapp"));
+        assert_eq!(vec![3, 4, 5], substring_positions("APP", "webapp"));
+        assert!(substring_positions("api", "webapp").is_empty());
+    }
+}
@@ -0,0 +1,23 @@
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+// The nearest directory at or above `dir` with a .git entry; it's a file in
+// worktrees and submodules.
+pub fn repo_root(dir: &Path) -> Option<&Path> {
+    dir.ancestors().find(|dir| dir.join(".git").exists())
+}
+
+// Nothing modified or untracked, so a fresh clone and install gets the project
+// back exactly. Ignored files such as node_modules don't count.
+pub fn is_clean(dir: &Path) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["status", "--porcelain"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|output| output.status.success() && output.stdout.is_empty())
+}
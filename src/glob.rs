@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use crate::model::home_dir;
+
+// A shell-style pattern from `exclude`: `*` and `?` stay inside one path
+// component, `**` crosses them. Without a slash it matches a directory name
+// anywhere; with one it matches the full path, where `~/` is the home directory
+// and a relative pattern may start at any depth.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: Vec<char>,
+    name_only: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        let pattern = pattern.trim().trim_end_matches('/');
+        let name_only = !pattern.contains('/');
+        let pattern = match pattern.strip_prefix("~/") {
+            Some(rest) => home_dir().join(rest).to_string_lossy().into_owned(),
+            None if name_only || pattern.starts_with(['/', '*']) => pattern.to_string(),
+            None => format!("**/{pattern}"),
+        };
+        Self {
+            pattern: pattern.chars().collect(),
+            name_only,
+        }
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let text = if self.name_only {
+            path.file_name().map(|name| name.to_string_lossy())
+        } else {
+            Some(path.to_string_lossy())
+        };
+        text.is_some_and(|text| wildcard(&self.pattern, &text.chars().collect::<Vec<_>>()))
+    }
+}
+
+pub fn excluded(globs: &[Glob], path: &Path) -> bool {
+    globs.iter().any(|glob| glob.matches(path))
+}
+
+pub fn compile(patterns: &[String]) -> Vec<Glob> {
+    patterns.iter().map(|pattern| Glob::new(pattern)).collect()
+}
+
+fn wildcard(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        // `**/` also matches no directories at all.
+        ['*', '*', '/', rest @ ..] => {
+            wildcard(rest, text)
+                || (0..text.len()).any(|i| text[i] == '/' && wildcard(rest, &text[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| wildcard(rest, &text[i..])),
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| wildcard(rest, &text[i..])),
+        ['?', rest @ ..] => text.first().is_some_and(|&c| c != '/') && wildcard(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && wildcard(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Glob;
+    use crate::model::home_dir;
+    use std::path::Path;
+
+    #[test]
+    fn matches_names_anywhere_and_paths_from_any_depth() {
+        let matches = |pattern: &str, path: &str| Glob::new(pattern).matches(Path::new(path));
+        assert!(matches("*.bak", "/srv/code/site.bak"));
+        assert!(!matches("*.bak", "/srv/code.bak/site"));
+        assert!(matches("legacy/*", "/srv/code/legacy/app"));
+        assert!(!matches("legacy/*", "/srv/code/legacy/app/packages"));
+        assert!(matches("/srv/**/vendor", "/srv/a/b/vendor"));
+        assert!(matches("/srv/**/vendor", "/srv/vendor"));
+        assert!(matches("client-?", "/work/client-a"));
+        assert!(!matches("/work", "/srv/work"));
+        assert!(Glob::new("~/archive").matches(&home_dir().join("archive")));
+    }
+}
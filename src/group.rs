@@ -0,0 +1,207 @@
+use ratatui::widgets::TableState;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::model::Data;
+
+// Artifacts under one top-level directory of a scanned root, e.g. everything in ~/code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    pub members: Vec<(PathBuf, u64)>,
+    pub bytes: u64,
+    pub expanded: bool,
+}
+
+pub enum GroupRow<'a> {
+    Header(&'a Group),
+    Member(&'a Path, u64),
+}
+
+pub struct GroupView {
+    pub groups: Vec<Group>,
+    pub state: TableState,
+}
+
+impl GroupView {
+    pub fn new(groups: Vec<Group>) -> Self {
+        Self {
+            groups,
+            state: TableState::default().with_selected(0),
+        }
+    }
+
+    // Group headers followed by the members of expanded groups.
+    pub fn rows(&self) -> Vec<GroupRow<'_>> {
+        let mut rows = Vec::new();
+        for group in &self.groups {
+            rows.push(GroupRow::Header(group));
+            if group.expanded {
+                rows.extend(
+                    group
+                        .members
+                        .iter()
+                        .map(|(path, bytes)| GroupRow::Member(path, *bytes)),
+                );
+            }
+        }
+        rows
+    }
+
+    // The group a row belongs to, and the member when it isn't a header.
+    fn locate(&self, row: usize) -> Option<(usize, Option<usize>)> {
+        let mut start = 0;
+        for (i, group) in self.groups.iter().enumerate() {
+            let len = 1 + if group.expanded {
+                group.members.len()
+            } else {
+                0
+            };
+            if row < start + len {
+                return Some((i, (row > start).then(|| row - start - 1)));
+            }
+            start += len;
+        }
+        None
+    }
+
+    pub fn selected_member(&self) -> Option<&Path> {
+        let (group, member) = self.locate(self.state.selected()?)?;
+        Some(&self.groups[group].members[member?].0)
+    }
+
+    // On a member row this collapses its group and moves onto the header.
+    pub fn toggle(&mut self) {
+        let Some((i, member)) = self.state.selected().and_then(|row| self.locate(row)) else {
+            return;
+        };
+        self.set_expanded(i, member.is_none() && !self.groups[i].expanded);
+    }
+
+    pub fn set_expanded(&mut self, i: usize, expanded: bool) {
+        self.groups[i].expanded = expanded;
+        let header = self.groups[..i]
+            .iter()
+            .map(|group| {
+                1 + if group.expanded {
+                    group.members.len()
+                } else {
+                    0
+                }
+            })
+            .sum();
+        self.state.select(Some(header));
+    }
+
+    pub fn collapse(&mut self) {
+        if let Some((i, _)) = self.state.selected().and_then(|row| self.locate(row)) {
+            self.set_expanded(i, false);
+        }
+    }
+
+    pub fn next_row(&mut self) {
+        let len = self.rows().len();
+        if len > 0 {
+            let i = self.state.selected().map_or(0, |i| (i + 1) % len);
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn previous_row(&mut self) {
+        let len = self.rows().len();
+        if len > 0 {
+            let i = self.state.selected().map_or(0, |i| (i + len - 1) % len);
+            self.state.select(Some(i));
+        }
+    }
+}
+
+// Keyed on the first path component below whichever root holds the artifact; an
+// artifact directly in a root is its own group. Largest groups come first.
+pub fn group_by_top_level(items: &[Data], roots: &[PathBuf]) -> Vec<Group> {
+    let mut grouped: BTreeMap<String, Vec<(PathBuf, u64)>> = BTreeMap::new();
+    for data in items {
+        grouped
+            .entry(top_level(&data.path, roots))
+            .or_default()
+            .push((data.path.clone(), data.bytes));
+    }
+    let mut groups: Vec<Group> = grouped
+        .into_iter()
+        .map(|(name, mut members)| {
+            members.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+            Group {
+                name,
+                bytes: members.iter().map(|(_, bytes)| bytes).sum(),
+                members,
+                expanded: false,
+            }
+        })
+        .collect();
+    groups.sort_by_key(|group| std::cmp::Reverse(group.bytes));
+    groups
+}
+
+fn top_level(path: &Path, roots: &[PathBuf]) -> String {
+    let relative = roots
+        .iter()
+        .filter_map(|root| path.strip_prefix(root).ok())
+        .min_by_key(|relative| relative.components().count());
+    match relative.and_then(|relative| relative.components().next()) {
+        Some(first) => format!("{}/", first.as_os_str().to_string_lossy()),
+        None => format!("{}/", path.parent().unwrap_or(path).display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GroupView, group_by_top_level};
+    use crate::model::Data;
+    use std::path::{Path, PathBuf};
+
+    fn artifact(path: &str, bytes: u64) -> Data {
+        Data {
+            path: PathBuf::from(path),
+            bytes,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn groups_by_first_component_under_the_root() {
+        let items = [
+            artifact("/home/ana/code/a/node_modules", 10),
+            artifact("/home/ana/Downloads/x/node_modules", 50),
+            artifact("/home/ana/code/b/node_modules", 30),
+            artifact("/home/ana/node_modules", 5),
+        ];
+        let groups = group_by_top_level(&items, &[PathBuf::from("/home/ana")]);
+        let summary: Vec<(&str, u64, usize)> = groups
+            .iter()
+            .map(|group| (group.name.as_str(), group.bytes, group.members.len()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("Downloads/", 50, 1),
+                ("code/", 40, 2),
+                ("node_modules/", 5, 1)
+            ],
+            summary
+        );
+
+        let mut view = GroupView::new(groups);
+        view.next_row();
+        view.toggle();
+        assert_eq!(5, view.rows().len());
+        view.next_row();
+        assert_eq!(
+            Some(Path::new("/home/ana/code/b/node_modules")),
+            view.selected_member()
+        );
+        view.toggle();
+        assert_eq!(3, view.rows().len());
+        assert_eq!(Some(1), view.state.selected());
+    }
+}
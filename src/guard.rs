@@ -0,0 +1,82 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::mounts::is_writable;
+
+#[cfg(unix)]
+pub fn running_as_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+pub fn running_as_root() -> bool {
+    false
+}
+
+// Removing a directory takes write access to it, to empty it, and to its parent,
+// to unlink it. Something locked deeper down still fails, but root-owned installs
+// and read-only mounts are caught before anyone selects them.
+pub fn can_delete(path: &Path) -> bool {
+    is_writable(path) && path.parent().is_some_and(is_writable)
+}
+
+// Anything that isn't inside one of the scanned roots, e.g. a path that only got
+// there through a symlink or a stale selection.
+pub fn outside_roots<'a>(
+    paths: impl IntoIterator<Item = &'a Path>,
+    roots: &[PathBuf],
+) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .filter(|path| !roots.iter().any(|root| path.starts_with(root)))
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+// Checked right before removal, so a link planted after the scan can't point
+// `remove_dir_all` somewhere else: the target itself mustn't be a symlink, and
+// with every link above it resolved it must still be inside one of `roots`.
+pub fn check_target(path: &Path, roots: &[PathBuf]) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.file_type().is_symlink() {
+        return Err(io::Error::other(
+            "it is a symlink, refusing to delete through it",
+        ));
+    }
+    let resolved = path.canonicalize()?;
+    let inside = roots
+        .iter()
+        .any(|root| resolved.starts_with(root.canonicalize().as_deref().unwrap_or(root)));
+    if !inside {
+        return Err(io::Error::other(format!(
+            "it resolves to {}, outside the scanned roots",
+            resolved.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::outside_roots;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn outside_roots_keeps_paths_not_under_any_root() {
+        let roots = [PathBuf::from("/home/ana"), PathBuf::from("/media/ana/disk")];
+        let paths = [
+            Path::new("/home/ana/app/node_modules"),
+            Path::new("/home/anabel/node_modules"),
+            Path::new("/media/ana/disk/web/node_modules"),
+            Path::new("/usr/lib/node_modules"),
+        ];
+        assert_eq!(
+            vec![
+                PathBuf::from("/home/anabel/node_modules"),
+                PathBuf::from("/usr/lib/node_modules")
+            ],
+            outside_roots(paths, &roots)
+        );
+    }
+}
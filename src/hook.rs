@@ -0,0 +1,130 @@
+use std::{
+    io,
+    path::Path,
+    process::{Command, Output},
+};
+
+use crate::model::ArtifactKind;
+
+// Commands from the config around deletions. Per-entry ones get {path} and
+// {project}; batch ones run once from the current directory.
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    // Runs instead of the built-in removal.
+    pub delete: Option<String>,
+    pub pre_delete: Option<String>,
+    pub post_delete: Option<String>,
+    pub pre_batch: Option<String>,
+    pub post_batch: Option<String>,
+}
+
+// Replaces {path} and {project} in a configured command, quoted for the shell.
+pub fn expand(template: &str, path: &Path) -> String {
+    template
+        .replace("{path}", &quote(path))
+        .replace("{project}", &quote(project(path)))
+}
+
+// Runs `template` for `path` from its project directory. Returns stdout and stderr
+// together; a non-zero exit becomes an error carrying that output.
+pub fn run(template: &str, path: &Path) -> io::Result<String> {
+    let command = expand(template, path);
+    let mut shell = shell(&command);
+    shell.current_dir(project(path));
+    execute(&command, shell)
+}
+
+pub fn run_batch(command: &str) -> io::Result<String> {
+    execute(command, shell(command))
+}
+
+fn execute(command: &str, mut shell: Command) -> io::Result<String> {
+    let output = shell.output()?;
+    let text = captured(&output);
+    if output.status.success() {
+        Ok(text)
+    } else if text.is_empty() {
+        Err(io::Error::other(format!("`{command}` {}", output.status)))
+    } else {
+        Err(io::Error::other(format!(
+            "`{command}` {}: {text}",
+            output.status
+        )))
+    }
+}
+
+fn project(path: &Path) -> &Path {
+    ArtifactKind::detect(path)
+        .unwrap_or_default()
+        .project_dir(path)
+}
+
+fn captured(output: &Output) -> String {
+    let mut text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(stderr.trim());
+    }
+    text
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(unix)]
+fn quote(path: &Path) -> String {
+    format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"))
+}
+
+#[cfg(not(unix))]
+fn quote(path: &Path) -> String {
+    format!("\"{}\"", path.to_string_lossy().replace('"', "\"\""))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{expand, run};
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn expand_quotes_path_and_project() {
+        assert_eq!(
+            r"trash-put '/srv/it'\''s/node_modules' # '/srv/it'\''s'",
+            expand(
+                "trash-put {path} # {project}",
+                Path::new("/srv/it's/node_modules")
+            )
+        );
+    }
+
+    #[test]
+    fn run_captures_output_and_fails_on_non_zero_exit() {
+        let project = std::env::temp_dir().join("nm-finder-rs-hook");
+        let modules = project.join("node_modules");
+        fs::create_dir_all(&modules).unwrap();
+
+        assert_eq!(
+            "node_modules\nwarning",
+            run("basename {path}; echo warning >&2", &modules).unwrap()
+        );
+        let error = run("echo nope; exit 3", &modules).unwrap_err();
+        assert!(error.to_string().ends_with(": nope"), "{error}");
+
+        fs::remove_dir_all(&project).unwrap();
+    }
+}
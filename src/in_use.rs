@@ -0,0 +1,86 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub fn find_in_use(targets: &[(PathBuf, PathBuf)]) -> Vec<(PathBuf, String)> {
+    let processes = open_paths();
+    targets
+        .iter()
+        .filter_map(|(target, project)| {
+            // Every cwd starts with an empty path, so a row without a project only
+            // counts when its own files are open.
+            let has_project = !project.as_os_str().is_empty();
+            let reason = processes
+                .iter()
+                .find(|process| {
+                    process
+                        .cwd
+                        .as_deref()
+                        .is_some_and(|cwd| has_project && cwd.starts_with(project))
+                        || process.open_files.iter().any(|p| p.starts_with(target))
+                })
+                .map(|process| format!("in use by {}", process.name))
+                .or_else(|| {
+                    has_project
+                        .then(|| pid_file(project))
+                        .flatten()
+                        .map(|f| format!("{f} present"))
+                })?;
+            Some((target.clone(), reason))
+        })
+        .collect()
+}
+
+struct Process {
+    name: String,
+    cwd: Option<PathBuf>,
+    open_files: Vec<PathBuf>,
+}
+
+fn open_paths() -> Vec<Process> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_string_lossy().parse::<u32>().ok()?;
+            let proc_dir = entry.path();
+            let comm = fs::read_to_string(proc_dir.join("comm")).unwrap_or_default();
+            let open_files = fs::read_dir(proc_dir.join("fd"))
+                .map(|fds| {
+                    fds.filter_map(|fd| fd.ok())
+                        .filter_map(|fd| fs::read_link(fd.path()).ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(Process {
+                name: format!("{} (pid {pid})", comm.trim()),
+                cwd: fs::read_link(proc_dir.join("cwd")).ok(),
+                open_files,
+            })
+        })
+        .collect()
+}
+
+fn pid_file(project: &Path) -> Option<String> {
+    fs::read_dir(project)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .find(|name| name.ends_with(".pid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_in_use;
+    use std::path::PathBuf;
+
+    #[test]
+    fn a_row_without_a_project_is_not_in_use_by_every_process() {
+        let targets = [(PathBuf::from("/nonexistent/node_modules"), PathBuf::new())];
+        assert!(find_in_use(&targets).is_empty());
+    }
+}
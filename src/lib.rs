@@ -0,0 +1,67 @@
+// The binary is a thin wrapper over these modules; `app::App::run_with_backend`
+// drives the TUI on any ratatui backend, e.g. a TestBackend in another crate.
+// Scanning and deleting are re-exported here with the error they fail with.
+pub mod analysis;
+pub mod app;
+pub mod bench;
+pub mod clean;
+pub mod cli;
+pub mod config;
+pub mod daemon;
+pub mod demo;
+pub mod dirs;
+pub mod drilldown;
+pub mod error;
+pub mod events;
+pub mod format;
+pub mod fuzzy;
+pub mod git;
+pub mod glob;
+pub mod group;
+pub mod guard;
+pub mod hook;
+pub mod in_use;
+pub mod json;
+pub mod list;
+pub mod message;
+pub mod model;
+pub mod monorepo;
+pub mod mounts;
+pub mod notify;
+pub mod palette;
+pub mod preview;
+pub mod priority;
+pub mod progress;
+pub mod project;
+pub mod remove;
+pub mod scanner;
+pub mod setup;
+pub mod space;
+pub mod state;
+pub mod suggest;
+pub mod terminal;
+pub mod update;
+
+pub use app::generate_data;
+pub use error::CleanerError;
+pub use remove::{delete, delete_entry};
+
+#[cfg(test)]
+mod tests {
+    use super::{CleanerError, generate_data};
+    use crate::config::Config;
+    use std::path::PathBuf;
+
+    #[test]
+    fn an_unreadable_root_is_a_scan_error() {
+        let root = PathBuf::from("/nonexistent/nm-finder-rs");
+        let config = Config {
+            root: Some(root.clone()),
+            ..Config::default()
+        };
+        match generate_data(&config) {
+            Err(CleanerError::Scan { path, .. }) => assert_eq!(root, path),
+            other => panic!("expected a scan error, got {other:?}"),
+        }
+    }
+}
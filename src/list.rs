@@ -0,0 +1,233 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+    time::SystemTime,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::generate_data;
+use crate::config::Config;
+use crate::error::CleanerError;
+use crate::events;
+use crate::format::{format_age, format_bytes, format_count};
+use crate::json::{Value, object};
+use crate::model::{Data, SortField, display_path};
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+const GB: u64 = 1_000_000_000;
+const HUNDRED_MB: u64 = 100_000_000;
+
+// Largest first unless `--sort` says otherwise.
+pub fn run(
+    sort: Option<(SortField, bool)>,
+    json: bool,
+    exact: bool,
+    config: &Config,
+) -> Result<(), CleanerError> {
+    let (field, reversed) = sort.unwrap_or((SortField::Size, true));
+    let mut items = generate_data(config)?;
+    items.sort_by(|a, b| {
+        let ordering = field.compare(a, b);
+        if reversed {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    if json {
+        println!("{}", to_json(&items));
+        return Ok(());
+    }
+    let color = env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal();
+    print!(
+        "{}",
+        render(
+            &items,
+            SystemTime::now(),
+            color,
+            config.absolute_paths,
+            exact
+        )
+    );
+    Ok(())
+}
+
+// {"schema_version": 1, "total_bytes": n, "directories": [{"path", "project",
+// "bytes", "files", "modified" (unix seconds or null), "kind", "project_type"}]}
+// `kind` and `project_type` are snake_case identifiers, not the UI badges; the
+// schema and its versioning are documented in the README.
+fn to_json(items: &[Data]) -> Value {
+    let number = |n: u64| Value::Number(n as f64);
+    let directories = items
+        .iter()
+        .map(|data| {
+            let modified = data
+                .modified
+                .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(Value::Null, |since| number(since.as_secs()));
+            object([
+                ("path", events::path(&data.path)),
+                ("project", events::path(&data.project)),
+                ("bytes", number(data.bytes)),
+                ("files", number(data.file_count)),
+                ("modified", modified),
+                ("kind", Value::String(data.kind.name().to_string())),
+                (
+                    "project_type",
+                    Value::String(data.project_type.name().to_string()),
+                ),
+            ])
+        })
+        .collect();
+    object([
+        events::schema_version(),
+        (
+            "total_bytes",
+            number(items.iter().map(|data| data.bytes).sum()),
+        ),
+        ("directories", Value::Array(directories)),
+    ])
+}
+
+// With `exact`, sizes are plain counts of allocated bytes that line up with `du -B1`.
+fn render(items: &[Data], now: SystemTime, color: bool, absolute: bool, exact: bool) -> String {
+    let size = |bytes: u64| {
+        if exact {
+            bytes.to_string()
+        } else {
+            format_bytes(bytes)
+        }
+    };
+    let paint = |code: &str, text: &str| {
+        if color {
+            format!("{code}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    };
+    let rows: Vec<(String, String, String)> = items
+        .iter()
+        .map(|data| {
+            let age = data
+                .modified
+                .and_then(|modified| now.duration_since(modified).ok())
+                .map_or_else(|| "-".to_string(), format_age);
+            (display_path(&data.path, absolute), size(data.bytes), age)
+        })
+        .collect();
+    let name_width = rows
+        .iter()
+        .map(|(name, ..)| name.width())
+        .max()
+        .unwrap_or(0)
+        .max("NAME".len());
+    let size_width = rows
+        .iter()
+        .map(|(_, size, _)| size.width())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let age_width = rows
+        .iter()
+        .map(|(.., age)| age.width())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+
+    let mut out = paint(
+        BOLD,
+        &format!(
+            "{:<name_width$}  {:>size_width$}  {:>age_width$}",
+            "NAME", "SIZE", "AGE"
+        ),
+    );
+    out.push('\n');
+    for (data, (name, size, age)) in items.iter().zip(&rows) {
+        let padding = " ".repeat(name_width - name.width());
+        let size = format!("{size:>size_width$}");
+        let size = match data.bytes {
+            bytes if bytes >= GB => paint(RED, &size),
+            bytes if bytes >= HUNDRED_MB => paint(YELLOW, &size),
+            _ => size,
+        };
+        out.push_str(&format!("{name}{padding}  {size}  {age:>age_width$}\n"));
+    }
+    let total: u64 = items.iter().map(|data| data.bytes).sum();
+    out.push_str(&paint(
+        DIM,
+        &format!(
+            "{} directories, {} in total",
+            format_count(items.len() as u64),
+            size(total)
+        ),
+    ));
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, to_json};
+    use crate::json::Value;
+    use crate::model::{ArtifactKind, Data};
+    use crate::project::ProjectType;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn render_aligns_name_size_and_age_columns() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 86_400);
+        let row = |path: &str, bytes: u64, days: u64| Data {
+            path: PathBuf::from(path),
+            bytes,
+            modified: Some(now - Duration::from_secs(days * 86_400)),
+            ..Default::default()
+        };
+        let items = vec![
+            row("/srv/web/node_modules", 1_288_490_189, 40),
+            row("/srv/api-gateway/node_modules", 12 * 1024, 0),
+        ];
+
+        assert_eq!(
+            "NAME                               SIZE    AGE\n\
+             /srv/web/node_modules           1.2 GiB    40d\n\
+             /srv/api-gateway/node_modules  12.0 KiB  today\n\
+             2 directories, 1.2 GiB in total\n",
+            render(&items, now, false, false, false)
+        );
+        assert!(
+            render(&items, now, false, false, true)
+                .ends_with("2 directories, 1288502477 in total\n")
+        );
+    }
+
+    #[test]
+    fn to_json_is_versioned() {
+        let items = vec![
+            Data {
+                path: PathBuf::from("/srv/web/node_modules"),
+                bytes: 2048,
+                ..Default::default()
+            },
+            Data {
+                path: PathBuf::from("/srv/web/.yarn/cache"),
+                kind: ArtifactKind::YarnCache,
+                project_type: ProjectType::NextJs,
+                ..Default::default()
+            },
+        ];
+        let json = to_json(&items);
+        assert_eq!(Some(&Value::Number(1.0)), json.get("schema_version"));
+        assert_eq!(Some(&Value::Number(2048.0)), json.get("total_bytes"));
+        let text = json.to_string();
+        assert!(text.contains(
+            r#""kind":"node_modules","modified":null,"path":"/srv/web/node_modules","project":"","project_type":"unknown""#
+        ));
+        assert!(text.contains(r#""kind":"yarn_cache""#));
+        assert!(text.contains(r#""project_type":"next_js""#));
+    }
+}
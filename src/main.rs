@@ -1,14 +1,66 @@
-mod app;
-mod model;
-mod scanner;
-
-use app::App;
-use color_eyre::Result;
+use color_eyre::{Result, eyre::eyre};
+use node_module_finder_rs::app::App;
+use node_module_finder_rs::cli::{self, Command};
+use node_module_finder_rs::config::Config;
+use node_module_finder_rs::format::{self, NumberFormat};
+use node_module_finder_rs::{bench, clean, daemon, guard, list, setup, terminal, update};
+use std::time::Duration;
 
 fn main() -> Result<()> {
-    color_eyre::install()?;
-    let terminal = ratatui::init();
-    let app_result = App::new().run(terminal);
-    ratatui::restore();
-    app_result
+    terminal::install_hooks()?;
+    let cli = cli::parse(std::env::args().skip(1))?;
+    let mut config = Config::load();
+    cli.overrides.apply(&mut config);
+    format::set_number_format(config.number_format.unwrap_or_else(NumberFormat::from_env));
+    if guard::running_as_root() && !cli.allow_root && cli.command != Command::Help {
+        return Err(eyre!(
+            "refusing to run as root, pass --allow-root if you really mean to"
+        ));
+    }
+
+    match cli.command {
+        Command::Help => {
+            print!("{}", cli::USAGE);
+            Ok(())
+        }
+        Command::List { sort, json, bytes } => Ok(list::run(sort, json, bytes, &config)?),
+        Command::Bench { root, runs, delete } if delete => bench::run_delete(root.as_deref(), runs),
+        Command::Bench { root, runs, .. } => {
+            let root = root.unwrap_or_else(|| config.root().to_path_buf());
+            bench::run(&root, runs, &config);
+            Ok(())
+        }
+        Command::Clean { preset, yes } => clean::run(&preset, yes, &config),
+        Command::Daemon {
+            listen,
+            interval,
+            preset,
+        } => daemon::run(
+            &listen,
+            Duration::from_secs(interval),
+            preset.as_deref(),
+            cli.allow_root,
+            &config,
+        ),
+        Command::SelfUpdate => update::run(),
+        Command::Tui => {
+            if !cli.demo && setup::needed() {
+                setup::run()?;
+                config = Config::load();
+                cli.overrides.apply(&mut config);
+            }
+            let terminal = terminal::init()?;
+            let app = if cli.demo {
+                Ok(App::demo(config))
+            } else {
+                App::new(config)
+            };
+            let app_result = app.map_err(Into::into).and_then(|app| app.run(terminal));
+            terminal::restore();
+            if let Some(summary) = app_result? {
+                println!("{summary}");
+            }
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,74 @@
+// Everything the TUI can be asked to do. Key presses are turned into messages by
+// `App::message_for`, and `App::update` is the only place that applies them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    // Sent every `TICK_RATE` without input.
+    Tick,
+
+    // The `:` command line.
+    OpenCommandLine,
+    CommandInput(char),
+    CommandBackspace,
+    SubmitCommand,
+    CancelCommand,
+
+    // The Ctrl-P jump prompt.
+    OpenJump,
+    JumpInput(char),
+    JumpBackspace,
+    ConfirmJump,
+    CancelJump,
+
+    // Whichever overlay is open: duplicates, groups, drilldown or a menu.
+    CloseOverlay,
+    OverlayNext,
+    OverlayPrevious,
+    ToggleSavings,
+    ToggleGroup,
+    CollapseGroup,
+    ShowGroupMember,
+    ToggleDrilldownSort,
+    ReverseDrilldown,
+    AddRoot,
+    RemoveRoot,
+    ApplySort { reversed: bool },
+    ApplySecondarySort { reversed: bool },
+    ClearSecondarySort,
+
+    ConfirmDeletion,
+    CancelDeletion,
+
+    NextRow,
+    PreviousRow,
+    NextColor,
+    PreviousColor,
+    ToggleSelection,
+    ToggleSelectAll,
+    DeleteHighlighted,
+    RequestDeletion,
+    QuickDelete,
+    ReverseSort,
+    OpenSortMenu,
+    TogglePreview,
+    OpenDrilldown,
+    OpenDuplicates,
+    OpenGroups,
+    OpenRootMenu,
+    SwitchKindTab { forward: bool },
+    CyclePathDisplay,
+    ToggleNested,
+    ToggleDelta,
+    JumpToLargest,
+    ToggleBookmark,
+    ToggleBookmarksFilter,
+    RefreshSizes,
+    OpenShell,
+    Quit,
+}
+
+// What `App::update` can't do by itself because it needs the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    Quit,
+    OpenShell,
+}
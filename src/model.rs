@@ -1,30 +1,368 @@
-use ratatui::style::{Color, palette::tailwind};
+use crate::format::format_bytes;
+use crate::project::ProjectType;
+use homedir::my_home;
+use ratatui::{
+    layout::Constraint,
+    style::{Color, palette::tailwind},
+};
+use std::{
+    cmp::Ordering,
+    env,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
 
-#[derive(Debug, Clone)]
+pub fn home_dir() -> &'static Path {
+    static HOME: OnceLock<PathBuf> = OnceLock::new();
+    HOME.get_or_init(|| {
+        let home = my_home().ok().flatten().unwrap_or_default();
+        home.canonicalize().unwrap_or(home)
+    })
+}
+
+// "~/code/app" for anything under the home directory, the absolute path otherwise.
+pub fn display_path(path: &Path, absolute: bool) -> String {
+    match path.strip_prefix(home_dir()) {
+        Ok(relative) if !absolute && relative.as_os_str().is_empty() => "~".to_string(),
+        Ok(relative) if !absolute => format!("~/{}", relative.to_string_lossy()),
+        _ => path.to_string_lossy().to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Data {
-    pub name: String,
-    pub size: String,
-    pub selected_for_deletion: String,
+    pub path: PathBuf,
+    pub project: PathBuf,
+    pub selected: bool,
+    pub bytes: u64,
+    pub file_count: u64,
+    pub cloud_files: u64,
+    pub modified: Option<SystemTime>,
+    pub kind: ArtifactKind,
+    pub project_type: ProjectType,
+    pub nested: Vec<PathBuf>,
+    pub status: Option<DeleteStatus>,
+    pub user: Option<String>,
+    // node_modules with no manifest or lockfile beside it, see `project::is_orphan`.
+    pub orphan: bool,
+    // The current user can't delete it, see `guard::can_delete`.
+    pub locked: bool,
+    // The size recorded by the previous scan; None if it wasn't there yet.
+    pub previous_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeleteStatus {
+    Queued,
+    Deleting,
+    Done,
+    Failed(String),
+}
+
+impl DeleteStatus {
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            DeleteStatus::Queued => "  …",
+            DeleteStatus::Deleting => "  ⟳",
+            DeleteStatus::Done => "  ✓",
+            DeleteStatus::Failed(_) => "  ✗",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ArtifactKind {
+    #[default]
+    NodeModules,
+    Pnpm,
+    YarnCache,
+    YarnPnpCache,
+    YarnUnplugged,
+    DenoCache,
+    BunCache,
+    TurboCache,
+    NxCache,
+}
+
+impl ArtifactKind {
+    pub const ALL: [Self; 9] = [
+        ArtifactKind::NodeModules,
+        ArtifactKind::Pnpm,
+        ArtifactKind::YarnCache,
+        ArtifactKind::YarnPnpCache,
+        ArtifactKind::YarnUnplugged,
+        ArtifactKind::DenoCache,
+        ArtifactKind::BunCache,
+        ArtifactKind::TurboCache,
+        ArtifactKind::NxCache,
+    ];
+
+    // As written in the `kinds` config key.
+    pub const fn name(self) -> &'static str {
+        match self {
+            ArtifactKind::NodeModules => "node_modules",
+            ArtifactKind::Pnpm => "pnpm",
+            ArtifactKind::YarnCache => "yarn_cache",
+            ArtifactKind::YarnPnpCache => "yarn_pnp",
+            ArtifactKind::YarnUnplugged => "yarn_unplugged",
+            ArtifactKind::DenoCache => "deno_cache",
+            ArtifactKind::BunCache => "bun_cache",
+            ArtifactKind::TurboCache => "turbo_cache",
+            ArtifactKind::NxCache => "nx_cache",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn global_caches() -> Vec<(Self, PathBuf)> {
+        let home = home_dir();
+        let deno = env::var_os("DENO_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                if cfg!(target_os = "macos") {
+                    home.join("Library/Caches/deno")
+                } else {
+                    env::var_os("XDG_CACHE_HOME")
+                        .map(PathBuf::from)
+                        .unwrap_or_else(|| home.join(".cache"))
+                        .join("deno")
+                }
+            });
+        let bun = env::var_os("BUN_INSTALL_CACHE_DIR")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("BUN_INSTALL").map(|p| PathBuf::from(p).join("install/cache")))
+            .unwrap_or_else(|| home.join(".bun/install/cache"));
+        vec![
+            (ArtifactKind::DenoCache, deno),
+            (ArtifactKind::BunCache, bun),
+        ]
+    }
+
+    pub fn detect(path: &Path) -> Option<Self> {
+        if let Some((kind, _)) = Self::global_caches()
+            .into_iter()
+            .find(|(_, cache)| cache == path)
+        {
+            return Some(kind);
+        }
+        Self::detect_local(path)
+    }
+
+    pub fn detect_local(path: &Path) -> Option<Self> {
+        if path.ends_with("node_modules") {
+            if path.join(".pnpm").is_dir() {
+                return Some(ArtifactKind::Pnpm);
+            }
+            return Some(ArtifactKind::NodeModules);
+        }
+        if path.ends_with(".yarn/cache") {
+            let project = path.parent()?.parent()?;
+            if project.join(".pnp.cjs").is_file() || project.join(".pnp.js").is_file() {
+                return Some(ArtifactKind::YarnPnpCache);
+            }
+            return Some(ArtifactKind::YarnCache);
+        }
+        if path.ends_with(".yarn/unplugged") {
+            return Some(ArtifactKind::YarnUnplugged);
+        }
+        // Monorepo task caches, rebuilt on the next run.
+        if path.ends_with(".turbo") {
+            return Some(ArtifactKind::TurboCache);
+        }
+        if path.ends_with(".nx/cache") {
+            return Some(ArtifactKind::NxCache);
+        }
+        None
+    }
+
+    pub fn project_dir(self, artifact: &Path) -> &Path {
+        let depth = match self {
+            ArtifactKind::NodeModules | ArtifactKind::Pnpm | ArtifactKind::TurboCache => 1,
+            ArtifactKind::YarnCache
+            | ArtifactKind::YarnPnpCache
+            | ArtifactKind::YarnUnplugged
+            | ArtifactKind::NxCache => 2,
+            ArtifactKind::DenoCache | ArtifactKind::BunCache => 0,
+        };
+        artifact.ancestors().nth(depth).unwrap_or(artifact)
+    }
+
+    pub const fn badge(self) -> Option<&'static str> {
+        match self {
+            ArtifactKind::NodeModules => None,
+            ArtifactKind::Pnpm => Some("pnpm"),
+            ArtifactKind::YarnCache => Some("yarn cache"),
+            ArtifactKind::YarnPnpCache => Some("yarn pnp"),
+            ArtifactKind::YarnUnplugged => Some("yarn unplugged"),
+            ArtifactKind::DenoCache => Some("deno cache"),
+            ArtifactKind::BunCache => Some("bun cache"),
+            ArtifactKind::TurboCache => Some("turbo cache"),
+            ArtifactKind::NxCache => Some("nx cache"),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        self.badge().unwrap_or("node_modules")
+    }
 }
 
 impl Data {
-    pub const fn ref_array(&self) -> [&String; 3] {
-        [&self.selected_for_deletion, &self.name, &self.size]
+    pub fn display_name(&self) -> String {
+        self.display_as(PathDisplay::Home)
     }
 
-    pub fn name(&self) -> &str {
-        &self.name
+    pub fn display_as(&self, mode: PathDisplay) -> String {
+        match mode {
+            PathDisplay::Home => display_path(&self.path, false),
+            PathDisplay::Absolute => display_path(&self.path, true),
+            PathDisplay::Project => self
+                .project
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.project.to_string_lossy().to_string()),
+        }
+    }
+
+    pub fn is_recent(&self, window: Duration) -> bool {
+        self.modified
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age < window)
     }
 
-    pub fn size_as_bytesize(&self) -> &str {
-        &self.size
+    pub fn size(&self) -> String {
+        format_bytes(self.bytes)
     }
+}
 
-    pub fn select(&self) -> &str {
-        &self.selected_for_deletion
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathDisplay {
+    #[default]
+    Home,
+    Absolute,
+    Project,
+}
+
+impl PathDisplay {
+    pub const fn next(self) -> Self {
+        match self {
+            PathDisplay::Home => PathDisplay::Absolute,
+            PathDisplay::Absolute => PathDisplay::Project,
+            PathDisplay::Project => PathDisplay::Home,
+        }
+    }
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            PathDisplay::Home => "Name",
+            PathDisplay::Absolute => "Absolute path",
+            PathDisplay::Project => "Project",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Name,
+    Size,
+    Modified,
+    Selected,
+    FileCount,
+    Depth,
+    Parent,
+}
+
+impl SortField {
+    pub const ALL: [SortField; 7] = [
+        SortField::Name,
+        SortField::Size,
+        SortField::Modified,
+        SortField::Selected,
+        SortField::FileCount,
+        SortField::Depth,
+        SortField::Parent,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(SortField::Name),
+            "size" => Some(SortField::Size),
+            "modified" | "age" => Some(SortField::Modified),
+            "selected" => Some(SortField::Selected),
+            "files" => Some(SortField::FileCount),
+            "depth" => Some(SortField::Depth),
+            "parent" => Some(SortField::Parent),
+            _ => None,
+        }
+    }
+
+    // A field and direction as typed. "age" runs opposite to "modified", so that
+    // "age desc" puts the oldest first like the AGE column suggests.
+    pub fn from_order(name: &str, descending: bool) -> Option<(Self, bool)> {
+        Some((Self::from_name(name)?, descending != (name == "age")))
+    }
+
+    // The inverse of `from_name`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            SortField::Name => "name",
+            SortField::Size => "size",
+            SortField::Modified => "modified",
+            SortField::Selected => "selected",
+            SortField::FileCount => "files",
+            SortField::Depth => "depth",
+            SortField::Parent => "parent",
+        }
+    }
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            SortField::Name => "Name",
+            SortField::Size => "Size",
+            SortField::Modified => "Modified",
+            SortField::Selected => "Selected",
+            SortField::FileCount => "File count",
+            SortField::Depth => "Path depth",
+            SortField::Parent => "Parent directory",
+        }
+    }
+
+    pub fn compare(self, a: &Data, b: &Data) -> Ordering {
+        match self {
+            SortField::Name => a.path.cmp(&b.path),
+            SortField::Size => a.bytes.cmp(&b.bytes),
+            SortField::Modified => a.modified.cmp(&b.modified),
+            SortField::Selected => a.selected.cmp(&b.selected),
+            SortField::FileCount => a.file_count.cmp(&b.file_count),
+            SortField::Depth => {
+                (a.path.components().count(), &a.path).cmp(&(b.path.components().count(), &b.path))
+            }
+            // The directory holding the project, so siblings like ~/code/experiments/*
+            // end up next to each other.
+            SortField::Parent => (a.project.parent(), &a.path).cmp(&(b.project.parent(), &b.path)),
+        }
     }
 }
 
+// Cycled with h/l; `palette` in the config picks one by name or position.
+pub const PALETTES: [(&str, TableColors); 6] = [
+    ("emerald", TableColors::new(&tailwind::EMERALD)),
+    ("indigo", TableColors::new(&tailwind::INDIGO)),
+    ("red", TableColors::new(&tailwind::RED)),
+    ("blue", TableColors::new(&tailwind::BLUE)),
+    ("color-blind", TableColors::color_blind()),
+    ("high-contrast", TableColors::high_contrast()),
+];
+
+pub fn palette_index(name: &str) -> Option<usize> {
+    PALETTES
+        .iter()
+        .position(|(palette, _)| palette.eq_ignore_ascii_case(name))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TableColors {
     pub buffer_bg: Color,
     pub header_bg: Color,
@@ -53,4 +391,298 @@ impl TableColors {
             footer_border_color: color.c400,
         }
     }
+
+    // Blue against orange stays apart with deuteranopia and protanopia, where the
+    // emerald and red highlights blur into the rows around them.
+    pub const fn color_blind() -> Self {
+        Self {
+            buffer_bg: tailwind::SLATE.c950,
+            header_bg: tailwind::BLUE.c900,
+            header_fg: tailwind::SLATE.c100,
+            row_fg: tailwind::SLATE.c200,
+            selected_row_style_fg: tailwind::ORANGE.c400,
+            selected_column_style_fg: tailwind::SKY.c300,
+            selected_cell_style_fg: tailwind::ORANGE.c600,
+            normal_row_color: tailwind::SLATE.c950,
+            alt_row_color: tailwind::SLATE.c900,
+            footer_border_color: tailwind::SKY.c400,
+        }
+    }
+
+    // Plain terminal colors on black, without zebra striping.
+    pub const fn high_contrast() -> Self {
+        Self {
+            buffer_bg: Color::Black,
+            header_bg: Color::White,
+            header_fg: Color::Black,
+            row_fg: Color::White,
+            selected_row_style_fg: Color::Yellow,
+            selected_column_style_fg: Color::Cyan,
+            selected_cell_style_fg: Color::Yellow,
+            normal_row_color: Color::Black,
+            alt_row_color: Color::Black,
+            footer_border_color: Color::White,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnWidth {
+    #[default]
+    Auto,
+    Fixed(u16),
+    Percent(u16),
+}
+
+impl ColumnWidth {
+    // "auto", "12" or "40%".
+    pub fn parse(value: &str) -> Option<Self> {
+        if value == "auto" {
+            return Some(Self::Auto);
+        }
+        match value.strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse()
+                .ok()
+                .filter(|percent| (1..=100).contains(percent))
+                .map(Self::Percent),
+            None => value
+                .parse()
+                .ok()
+                .filter(|width| *width > 0)
+                .map(Self::Fixed),
+        }
+    }
+
+    pub const fn constraint(self, auto: Constraint) -> Constraint {
+        match self {
+            Self::Auto => auto,
+            Self::Fixed(width) => Constraint::Length(width),
+            Self::Percent(percent) => Constraint::Percentage(percent),
+        }
+    }
+}
+
+// Per-column overrides of the main table's widths; anything left on auto keeps
+// the built-in width.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColumnWidths {
+    pub name: ColumnWidth,
+    pub user: ColumnWidth,
+    pub kind: ColumnWidth,
+    pub trend: ColumnWidth,
+    pub delta: ColumnWidth,
+    pub bar: ColumnWidth,
+    pub share: ColumnWidth,
+    pub size: ColumnWidth,
+}
+
+impl ColumnWidths {
+    // Comma-separated columns and widths, e.g. "name 60%, size 12, type auto".
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut widths = Self::default();
+        for entry in value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (column, width) = entry.split_once(char::is_whitespace)?;
+            let width = ColumnWidth::parse(width.trim())?;
+            let slot = match column {
+                "name" | "path" => &mut widths.name,
+                "user" => &mut widths.user,
+                "type" => &mut widths.kind,
+                "trend" => &mut widths.trend,
+                "delta" => &mut widths.delta,
+                "bar" => &mut widths.bar,
+                "share" => &mut widths.share,
+                "size" => &mut widths.size,
+                _ => return None,
+            };
+            *slot = width;
+        }
+        Some(widths)
+    }
+}
+
+// Where the Size column has turned fully yellow and fully red, as shares of the
+// largest entry. Smaller sizes fade from green towards yellow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Heatmap {
+    pub yellow: f64,
+    pub red: f64,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Self {
+            yellow: 0.1,
+            red: 0.5,
+        }
+    }
+}
+
+impl Heatmap {
+    // Two percentages such as "10, 50".
+    pub fn parse(value: &str) -> Option<Self> {
+        let (yellow, red) = value.split_once(',')?;
+        let yellow: f64 = yellow.trim().trim_end_matches('%').parse().ok()?;
+        let red: f64 = red.trim().trim_end_matches('%').parse().ok()?;
+        (0.0 < yellow && yellow < red && red <= 100.0).then_some(Self {
+            yellow: yellow / 100.0,
+            red: red / 100.0,
+        })
+    }
+
+    pub fn color(&self, bytes: u64, largest: u64) -> Color {
+        let share = if largest == 0 {
+            0.0
+        } else {
+            bytes as f64 / largest as f64
+        };
+        let (green, yellow, red) = (
+            tailwind::GREEN.c400,
+            tailwind::YELLOW.c400,
+            tailwind::RED.c400,
+        );
+        if share < self.yellow {
+            mix(green, yellow, share / self.yellow)
+        } else if share < self.red {
+            mix(
+                yellow,
+                red,
+                (share - self.yellow) / (self.red - self.yellow),
+            )
+        } else {
+            red
+        }
+    }
+}
+
+fn mix(from: Color, to: Color, amount: f64) -> Color {
+    match (from, to) {
+        (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+            let channel = |a: u8, b: u8| {
+                (f64::from(a) + (f64::from(b) - f64::from(a)) * amount).round() as u8
+            };
+            Color::Rgb(channel(r1, r2), channel(g1, g2), channel(b1, b2))
+        }
+        _ => to,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ArtifactKind, ColumnWidth, ColumnWidths, Data, Heatmap, SortField, display_path, home_dir,
+    };
+    use ratatui::style::palette::tailwind;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn heatmap_fades_from_green_to_red_by_share_of_the_largest() {
+        let heatmap = Heatmap::parse("10, 50%").unwrap();
+        assert_eq!(Heatmap::default(), heatmap);
+        assert_eq!(tailwind::GREEN.c400, heatmap.color(0, 1000));
+        assert_eq!(tailwind::YELLOW.c400, heatmap.color(100, 1000));
+        assert_eq!(tailwind::RED.c400, heatmap.color(800, 1000));
+        assert_ne!(tailwind::YELLOW.c400, heatmap.color(300, 1000));
+        assert_eq!(None, Heatmap::parse("50, 10"));
+    }
+
+    #[test]
+    fn column_widths_parse_fixed_and_percentage_widths() {
+        let widths = ColumnWidths::parse("name 60%, size 12, type auto").unwrap();
+        assert_eq!(ColumnWidth::Percent(60), widths.name);
+        assert_eq!(ColumnWidth::Fixed(12), widths.size);
+        assert_eq!(ColumnWidth::Auto, widths.kind);
+        assert_eq!(None, ColumnWidths::parse("name 120%"));
+        assert_eq!(None, ColumnWidths::parse("colour 10"));
+    }
+
+    #[test]
+    fn parent_sort_groups_projects_by_the_directory_holding_them() {
+        let data = |path: &str| Data {
+            path: PathBuf::from(path).join("node_modules"),
+            project: PathBuf::from(path),
+            ..Data::default()
+        };
+        let mut items = [
+            data("/code/experiments/b"),
+            data("/code/work/a"),
+            data("/code/experiments/a/nested"),
+            data("/code/experiments/c"),
+        ];
+        items.sort_by(|a, b| SortField::Parent.compare(a, b));
+        let order: Vec<_> = items.iter().map(|data| data.project.clone()).collect();
+        assert_eq!(
+            vec![
+                PathBuf::from("/code/experiments/b"),
+                PathBuf::from("/code/experiments/c"),
+                PathBuf::from("/code/experiments/a/nested"),
+                PathBuf::from("/code/work/a"),
+            ],
+            order
+        );
+
+        items.sort_by(|a, b| SortField::Depth.compare(a, b));
+        assert_eq!(Path::new("/code/experiments/a/nested"), items[3].project);
+    }
+
+    #[test]
+    fn detect_recognizes_yarn_berry_layouts() {
+        let project = std::env::temp_dir().join("nm-finder-rs-detect-yarn");
+        let _ = fs::remove_dir_all(&project);
+        fs::create_dir_all(project.join(".yarn/cache")).unwrap();
+        fs::create_dir_all(project.join(".yarn/unplugged")).unwrap();
+
+        let cache = project.join(".yarn/cache");
+        assert_eq!(Some(ArtifactKind::YarnCache), ArtifactKind::detect(&cache));
+        fs::write(project.join(".pnp.cjs"), "").unwrap();
+        assert_eq!(
+            Some(ArtifactKind::YarnPnpCache),
+            ArtifactKind::detect(&cache)
+        );
+        assert_eq!(
+            Some(ArtifactKind::YarnUnplugged),
+            ArtifactKind::detect(&project.join(".yarn/unplugged"))
+        );
+        assert_eq!(project, ArtifactKind::YarnCache.project_dir(&cache));
+        assert_eq!(None, ArtifactKind::detect(&project.join(".yarn")));
+
+        fs::remove_dir_all(&project).unwrap();
+    }
+
+    #[test]
+    fn detect_recognizes_monorepo_caches() {
+        let project = Path::new("/code/monorepo");
+        let nx = project.join(".nx/cache");
+        assert_eq!(Some(ArtifactKind::NxCache), ArtifactKind::detect(&nx));
+        assert_eq!(project, ArtifactKind::NxCache.project_dir(&nx));
+        let turbo = project.join("apps/web/.turbo");
+        assert_eq!(Some(ArtifactKind::TurboCache), ArtifactKind::detect(&turbo));
+        assert_eq!(
+            project.join("apps/web"),
+            ArtifactKind::TurboCache.project_dir(&turbo)
+        );
+
+        // Nx keeps its workspace data next to the cache; only the cache goes.
+        assert_eq!(None, ArtifactKind::detect(&project.join(".nx")));
+        assert_eq!(
+            None,
+            ArtifactKind::detect(&project.join(".nx/workspace-data"))
+        );
+        assert_eq!(None, ArtifactKind::detect(&project.join("cache")));
+    }
+
+    #[test]
+    fn display_path_abbreviates_only_the_home_prefix() {
+        let app = home_dir().join("code/app");
+        assert_eq!("~/code/app", display_path(&app, false));
+        assert_eq!(app.to_string_lossy(), display_path(&app, true));
+        assert_eq!("~", display_path(home_dir(), false));
+        assert_eq!("/srv/app", display_path(Path::new("/srv/app"), false));
+    }
 }
@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use crate::model::{Data, home_dir};
+
+// Folds every artifact whose project lives inside another artifact's project into
+// that top-level row. Paths already covered by the row are not counted twice.
+pub fn collapse_nested(items: &[Data]) -> Vec<Data> {
+    let mut by_depth: Vec<&Data> = items.iter().collect();
+    by_depth.sort_by_key(|data| data.project.components().count());
+
+    let mut rows: Vec<Data> = Vec::new();
+    for data in by_depth {
+        let parent = rows
+            .iter_mut()
+            .filter(|row| can_contain(row))
+            .find(|row| data.project.starts_with(&row.project));
+        match parent {
+            Some(row) => {
+                let covered = std::iter::once(&row.path)
+                    .chain(&row.nested)
+                    .any(|path| data.path.starts_with(path));
+                if !covered {
+                    row.bytes += data.bytes;
+                    row.file_count += data.file_count;
+                    row.cloud_files += data.cloud_files;
+                    row.previous_bytes = row
+                        .previous_bytes
+                        .map(|bytes| bytes + data.previous_bytes.unwrap_or(0));
+                    row.modified = row.modified.max(data.modified);
+                }
+                row.nested.push(data.path.clone());
+                row.nested.extend(data.nested.iter().cloned());
+            }
+            None => rows.push(data.clone()),
+        }
+    }
+    rows
+}
+
+// Global caches and stray installs in the home directory are not projects, so
+// they never absorb other rows.
+fn can_contain(row: &Data) -> bool {
+    row.project != row.path && row.project != home_dir() && row.project != Path::new("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::collapse_nested;
+    use crate::model::Data;
+    use std::path::PathBuf;
+
+    fn artifact(project: &str, bytes: u64) -> Data {
+        Data {
+            path: PathBuf::from(project).join("node_modules"),
+            project: PathBuf::from(project),
+            bytes,
+            file_count: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn collapse_nested_aggregates_workspace_packages() {
+        let items = vec![
+            artifact("/src/mono/packages/ui", 10),
+            artifact("/src/mono", 100),
+            artifact("/src/mono/apps/web", 20),
+            artifact("/src/other", 5),
+        ];
+        let rows = collapse_nested(&items);
+
+        assert_eq!(2, rows.len());
+        assert_eq!(PathBuf::from("/src/mono"), rows[0].project);
+        assert_eq!(130, rows[0].bytes);
+        assert_eq!(3, rows[0].file_count);
+        assert_eq!(2, rows[0].nested.len());
+        assert!(rows[1].nested.is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::model::home_dir;
+
+// Volumes the desktop mounts on the user's behalf: removable drives under
+// /media/$USER or /run/media/$USER on Linux, and everything in /Volumes on macOS.
+pub fn user_mounts() -> Vec<PathBuf> {
+    let user = env::var("USER").ok().or_else(|| {
+        home_dir()
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    });
+    let mut parents = vec![PathBuf::from("/Volumes")];
+    if let Some(user) = user {
+        parents.push(Path::new("/media").join(&user));
+        parents.push(Path::new("/run/media").join(user));
+    }
+
+    parents
+        .iter()
+        .filter_map(|parent| fs::read_dir(parent).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        // The boot volume shows up in /Volumes as a symlink to /.
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.path())
+        .filter(|path| is_writable(path))
+        .collect()
+}
+
+#[cfg(unix)]
+pub fn is_writable(path: &Path) -> bool {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+    CString::new(path.as_os_str().as_bytes())
+        .is_ok_and(|path| unsafe { libc::access(path.as_ptr(), libc::W_OK) } == 0)
+}
+
+#[cfg(not(unix))]
+pub fn is_writable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|meta| !meta.permissions().readonly())
+}
+
+const NETWORK_FILESYSTEMS: [&str; 10] = [
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smbfs",
+    "smb3",
+    "fuse.sshfs",
+    "sshfs",
+    "afpfs",
+    "davfs",
+    "fuse.rclone",
+];
+
+pub fn network_mounts() -> Vec<PathBuf> {
+    mount_table()
+        .into_iter()
+        .filter(|(_, fs_type)| NETWORK_FILESYSTEMS.contains(&fs_type.as_str()))
+        .map(|(mount_point, _)| mount_point)
+        .collect()
+}
+
+// The btrfs or ZFS mounts with snapshots that any of `paths` is on, each once, since
+// deleting there won't free space until the snapshots go too. The mount table is
+// read once for the whole batch; on macOS that means running `mount`.
+pub fn snapshot_mounts<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Vec<(PathBuf, String)> {
+    let table = mount_table();
+    let mut checked: Vec<&Path> = Vec::new();
+    let mut mounts = Vec::new();
+    for path in paths {
+        let Some((mount_point, fs_type)) = containing_mount(&table, path) else {
+            continue;
+        };
+        if checked.contains(&mount_point.as_path()) {
+            continue;
+        }
+        checked.push(mount_point);
+        if has_snapshots(mount_point, fs_type) {
+            mounts.push((mount_point.clone(), fs_type.clone()));
+        }
+    }
+    mounts
+}
+
+fn containing_mount<'a>(
+    table: &'a [(PathBuf, String)],
+    path: &Path,
+) -> Option<&'a (PathBuf, String)> {
+    table
+        .iter()
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+}
+
+fn has_snapshots(mount_point: &Path, fs_type: &str) -> bool {
+    match fs_type {
+        // snapper keeps them in .snapshots at the subvolume root, timeshift in its own tree.
+        "btrfs" => {
+            mount_point.join(".snapshots").is_dir() || Path::new("/timeshift-btrfs").is_dir()
+        }
+        "zfs" => fs::read_dir(mount_point.join(".zfs/snapshot"))
+            .is_ok_and(|mut snapshots| snapshots.next().is_some()),
+        _ => false,
+    }
+}
+
+fn mount_table() -> Vec<(PathBuf, String)> {
+    match fs::read_to_string("/proc/self/mounts") {
+        Ok(contents) => parse_proc_mounts(&contents),
+        Err(_) => std::process::Command::new("mount")
+            .output()
+            .map(|output| parse_mount_output(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or_default(),
+    }
+}
+
+// "server:/export /mnt/nfs nfs4 rw,relatime 0 0", with spaces escaped as \040.
+fn parse_proc_mounts(contents: &str) -> Vec<(PathBuf, String)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            let mount_point = fields.next()?.replace("\\040", " ");
+            Some((PathBuf::from(mount_point), fields.next()?.to_string()))
+        })
+        .collect()
+}
+
+// BSD/macOS `mount`: "//me@nas/share on /Volumes/share (smbfs, nodev, nosuid)".
+fn parse_mount_output(output: &str) -> Vec<(PathBuf, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount_point, options) = rest.rsplit_once(" (")?;
+            let fs_type = options.split([',', ')']).next()?.trim();
+            Some((PathBuf::from(mount_point), fs_type.to_string()))
+        })
+        .collect()
+}
+
+// Spinning disks report queue/rotational = 1 in sysfs; partitions keep their queue
+// settings on the parent device.
+#[cfg(target_os = "linux")]
+pub fn is_rotational(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let Ok(meta) = fs::metadata(path) else {
+        return false;
+    };
+    let dev = meta.dev();
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    let device = PathBuf::from(format!("/sys/dev/block/{major}:{minor}"));
+    [
+        device.join("queue/rotational"),
+        device.join("../queue/rotational"),
+    ]
+    .iter()
+    .find_map(|file| fs::read_to_string(file).ok())
+    .is_some_and(|flag| flag.trim() == "1")
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_rotational(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{containing_mount, parse_mount_output, parse_proc_mounts};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn mount_tables_yield_mount_points_and_types() {
+        assert_eq!(
+            vec![
+                (PathBuf::from("/"), "ext4".to_string()),
+                (PathBuf::from("/mnt/team share"), "nfs4".to_string()),
+            ],
+            parse_proc_mounts(
+                "/dev/sda1 / ext4 rw 0 0\nnas:/export /mnt/team\\040share nfs4 rw 0 0\n"
+            )
+        );
+        assert_eq!(
+            vec![(PathBuf::from("/Volumes/share"), "smbfs".to_string())],
+            parse_mount_output("//me@nas/share on /Volumes/share (smbfs, nodev, nosuid)\n")
+        );
+    }
+
+    #[test]
+    fn the_deepest_mount_holds_a_path() {
+        let table = parse_proc_mounts("/dev/sda1 / ext4 rw 0 0\ntank/home /home zfs rw 0 0\n");
+        assert_eq!(
+            Some(&(PathBuf::from("/home"), "zfs".to_string())),
+            containing_mount(&table, Path::new("/home/ana/app/node_modules"))
+        );
+        assert_eq!(
+            Some(&(PathBuf::from("/"), "ext4".to_string())),
+            containing_mount(&table, Path::new("/srv/app/node_modules"))
+        );
+    }
+}
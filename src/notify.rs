@@ -0,0 +1,105 @@
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use crate::events::schema_version;
+use crate::format::{format_bytes, format_count};
+use crate::json::{Value, object};
+
+pub struct Summary<'a> {
+    pub host: &'a str,
+    pub preset: &'a str,
+    pub deleted: usize,
+    pub bytes_freed: u64,
+    pub failures: usize,
+}
+
+impl Summary<'_> {
+    // "text" is what Slack and most chat webhooks display; the rest is for scripts.
+    pub fn to_json(&self) -> Value {
+        let mut text = format!(
+            "{}: `{}` cleanup deleted {} directories, freed {}",
+            self.host,
+            self.preset,
+            format_count(self.deleted as u64),
+            format_bytes(self.bytes_freed)
+        );
+        if self.failures > 0 {
+            text.push_str(&format!(", {} failed", format_count(self.failures as u64)));
+        }
+        object([
+            schema_version(),
+            ("text", Value::String(text)),
+            ("host", Value::String(self.host.to_string())),
+            ("preset", Value::String(self.preset.to_string())),
+            ("deleted", Value::Number(self.deleted as f64)),
+            ("bytes_freed", Value::Number(self.bytes_freed as f64)),
+            ("failures", Value::Number(self.failures as f64)),
+        ])
+    }
+}
+
+// Goes through curl rather than an HTTP client of our own, so https and proxies
+// work the way they do everywhere else on the machine.
+pub fn post(url: &str, body: &Value) -> io::Result<()> {
+    let mut curl = Command::new("curl")
+        .args(["-fsS", "-X", "POST", "-H", "Content-Type: application/json"])
+        .args(["--data-binary", "@-", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = curl.stdin.take() {
+        stdin.write_all(body.to_string().as_bytes())?;
+    }
+    let output = curl.wait_with_output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(unix)]
+pub fn hostname() -> String {
+    let mut buffer = [0u8; 256];
+    if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } != 0 {
+        return String::from("unknown host");
+    }
+    let len = buffer.iter().position(|&b| b == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..len]).into_owned()
+}
+
+#[cfg(not(unix))]
+pub fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_else(|_| String::from("unknown host"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Summary;
+    use crate::json::{self, Value};
+
+    #[test]
+    fn summary_has_a_chat_text_and_the_raw_numbers() {
+        let summary = Summary {
+            host: "ci-runner-3",
+            preset: "weekly",
+            deleted: 12,
+            bytes_freed: 3 * 1024 * 1024 * 1024,
+            failures: 1,
+        };
+        let value = json::parse(&summary.to_json().to_string()).unwrap();
+        assert_eq!(
+            Some(&Value::String(
+                "ci-runner-3: `weekly` cleanup deleted 12 directories, freed 3.0 GiB, 1 failed"
+                    .to_string()
+            )),
+            value.get("text")
+        );
+        assert_eq!(Some(&Value::Number(1.0)), value.get("failures"));
+    }
+}
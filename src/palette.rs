@@ -0,0 +1,301 @@
+use bytesize::ByteSize;
+use std::{collections::BTreeSet, path::PathBuf, str::FromStr, time::Duration};
+
+use crate::git;
+use crate::model::{Data, SortField, home_dir};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Select(Criteria),
+    Deselect(Criteria),
+    Filter(String),
+    Preset(String),
+    SavePreset(String),
+    Tag(String),
+    Untag(String),
+    // An empty note removes it.
+    Note(String),
+    Sort(SortField, bool),
+    AddRoot(PathBuf),
+    RemoveRoot(PathBuf),
+    ExportSelection(PathBuf),
+    ImportSelection(PathBuf),
+    Suggest,
+    Delete,
+    Quit,
+}
+
+// Every condition has to match; no conditions (or "all") matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Criteria(pub Vec<Condition>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    SizeAbove(u64),
+    SizeBelow(u64),
+    OlderThan(Duration),
+    NewerThan(Duration),
+    Tagged(String),
+    NewSinceLastScan,
+    Orphan,
+}
+
+impl Criteria {
+    pub fn matches(&self, data: &Data, tags: Option<&BTreeSet<String>>) -> bool {
+        self.0.iter().all(|condition| condition.matches(data, tags))
+    }
+
+    pub fn parse(input: &str) -> Result<Self, String> {
+        input
+            .split_whitespace()
+            .filter(|term| *term != "all")
+            .map(Condition::parse)
+            .collect::<Result<_, _>>()
+            .map(Criteria)
+    }
+}
+
+impl Condition {
+    pub fn matches(&self, data: &Data, tags: Option<&BTreeSet<String>>) -> bool {
+        let age = || data.modified.and_then(|modified| modified.elapsed().ok());
+        match self {
+            Condition::SizeAbove(bytes) => data.bytes > *bytes,
+            Condition::SizeBelow(bytes) => data.bytes < *bytes,
+            Condition::OlderThan(limit) => age().is_some_and(|age| age > *limit),
+            Condition::NewerThan(limit) => age().is_some_and(|age| age < *limit),
+            Condition::Tagged(tag) => tags.is_some_and(|tags| tags.contains(tag)),
+            Condition::NewSinceLastScan => data.previous_bytes.is_none(),
+            Condition::Orphan => data.orphan,
+        }
+    }
+
+    fn parse(input: &str) -> Result<Self, String> {
+        if let Some(tag) = input.strip_prefix("tag:") {
+            return Ok(Condition::Tagged(tag.to_string()));
+        }
+        match input {
+            "is:new" => return Ok(Condition::NewSinceLastScan),
+            "is:orphan" => return Ok(Condition::Orphan),
+            _ => {}
+        }
+        let above = input.contains('>');
+        match input.split_once(['>', '<']) {
+            Some(("size", size)) => {
+                let bytes = ByteSize::from_str(size)
+                    .map_err(|_| format!("invalid size '{size}'"))?
+                    .as_u64();
+                Ok(if above {
+                    Condition::SizeAbove(bytes)
+                } else {
+                    Condition::SizeBelow(bytes)
+                })
+            }
+            Some(("age", days)) => {
+                let days: u64 = days
+                    .trim_end_matches('d')
+                    .parse()
+                    .map_err(|_| format!("invalid age '{days}', try 90d"))?;
+                let limit = Duration::from_secs(days * 86_400);
+                Ok(if above {
+                    Condition::OlderThan(limit)
+                } else {
+                    Condition::NewerThan(limit)
+                })
+            }
+            _ => Err(format!(
+                "unknown condition '{input}', try size>1GB or age>90d"
+            )),
+        }
+    }
+}
+
+// "tag:name" filters by tag, "is:new" to rows the previous scan didn't find,
+// "is:orphan" to node_modules no project owns any more, "is:bookmarked" to
+// bookmarks, "is:git" to projects inside a git repository; anything else is a
+// case-insensitive path substring.
+pub fn filter_matches(
+    query: &str,
+    data: &Data,
+    tags: Option<&BTreeSet<String>>,
+    bookmarked: bool,
+) -> bool {
+    match query {
+        "is:new" => return Condition::NewSinceLastScan.matches(data, tags),
+        "is:orphan" => return Condition::Orphan.matches(data, tags),
+        "is:bookmarked" => return bookmarked,
+        "is:git" => return git::repo_root(&data.project).is_some(),
+        _ => {}
+    }
+    match query.strip_prefix("tag:") {
+        Some(tag) => Condition::Tagged(tag.to_string()).matches(data, tags),
+        None => data
+            .display_name()
+            .to_lowercase()
+            .contains(&query.to_lowercase()),
+    }
+}
+
+pub fn parse(input: &str) -> Result<Action, String> {
+    let input = input.trim();
+    let (command, args) = input.split_once(' ').unwrap_or((input, ""));
+    let args = args.trim();
+    match command {
+        "select" | "sel" => Criteria::parse(args).map(Action::Select),
+        "deselect" | "unselect" => Criteria::parse(args).map(Action::Deselect),
+        "filter" | "f" => Ok(Action::Filter(args.to_string())),
+        "preset" => match args.split_once(' ') {
+            Some(("save", name)) if !name.trim().is_empty() => {
+                Ok(Action::SavePreset(name.trim().to_string()))
+            }
+            None if !args.is_empty() && args != "save" => Ok(Action::Preset(args.to_string())),
+            _ => Err("usage: preset <name> | preset save <name>".to_string()),
+        },
+        "tag" | "untag" if args.is_empty() || args.contains(char::is_whitespace) => {
+            Err(format!("usage: {command} <name>"))
+        }
+        "tag" => Ok(Action::Tag(args.to_string())),
+        "untag" => Ok(Action::Untag(args.to_string())),
+        "note" => Ok(Action::Note(args.replace('\t', " "))),
+        "sort" => parse_sort(args),
+        "root" => match args.split_once(' ') {
+            Some(("add", path)) if !path.trim().is_empty() => {
+                Ok(Action::AddRoot(root_path(path.trim())))
+            }
+            Some(("remove" | "rm", path)) if !path.trim().is_empty() => {
+                Ok(Action::RemoveRoot(root_path(path.trim())))
+            }
+            _ => Err("usage: root add <path> | root remove <path>".to_string()),
+        },
+        "export" | "import" if args.is_empty() => Err(format!("usage: {command} <file>")),
+        "export" => Ok(Action::ExportSelection(root_path(args))),
+        "import" => Ok(Action::ImportSelection(root_path(args))),
+        "suggest" => Ok(Action::Suggest),
+        "delete" | "d" => Ok(Action::Delete),
+        "quit" | "q" => Ok(Action::Quit),
+        "" => Err("empty command".to_string()),
+        _ => Err(format!("unknown command '{command}'")),
+    }
+}
+
+pub fn root_path(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) => home_dir().join(rest.trim_start_matches('/')),
+        None => PathBuf::from(path),
+    }
+}
+
+fn parse_sort(args: &str) -> Result<Action, String> {
+    let (field, direction) = args.split_once(' ').unwrap_or((args, "asc"));
+    let descending = match direction.trim() {
+        "asc" => false,
+        "desc" => true,
+        direction => return Err(format!("unknown direction '{direction}', use asc or desc")),
+    };
+    let (field, reversed) =
+        SortField::from_order(field, descending).ok_or(format!("unknown sort field '{field}'"))?;
+    Ok(Action::Sort(field, reversed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Condition, Criteria, filter_matches, parse};
+    use crate::model::{Data, SortField, home_dir};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn parse_reads_commands_with_arguments() {
+        assert_eq!(
+            Ok(Action::Select(Criteria(vec![Condition::SizeAbove(
+                1_000_000_000
+            )]))),
+            parse("select size>1GB")
+        );
+        assert_eq!(
+            Ok(Action::Deselect(Criteria(vec![
+                Condition::SizeBelow(100_000_000),
+                Condition::OlderThan(Duration::from_secs(90 * 86_400)),
+            ]))),
+            parse("deselect size<100MB age>90d")
+        );
+        assert_eq!(Ok(Action::Select(Criteria::default())), parse("select all"));
+        assert_eq!(
+            Ok(Action::SavePreset("big-and-stale".to_string())),
+            parse("preset save big-and-stale")
+        );
+        assert_eq!(Ok(Action::Filter("work".to_string())), parse("filter work"));
+        assert_eq!(
+            Ok(Action::Sort(SortField::Size, true)),
+            parse("sort size desc")
+        );
+        assert_eq!(
+            Ok(Action::Select(Criteria(vec![Condition::Tagged(
+                "later".to_string()
+            )]))),
+            parse("select tag:later")
+        );
+        assert_eq!(
+            Ok(Action::Select(Criteria(vec![Condition::NewSinceLastScan]))),
+            parse("select is:new")
+        );
+        assert_eq!(
+            Ok(Action::Select(Criteria(vec![Condition::Orphan]))),
+            parse("select is:orphan")
+        );
+        assert_eq!(
+            Ok(Action::Tag("ask-team".to_string())),
+            parse("tag ask-team")
+        );
+        assert_eq!(
+            Ok(Action::Note("client project, ask first".to_string())),
+            parse("note  client project, ask first")
+        );
+        assert_eq!(Ok(Action::Note(String::new())), parse("note"));
+        assert_eq!(
+            Ok(Action::AddRoot(PathBuf::from("/srv/work"))),
+            parse("root add /srv/work")
+        );
+        assert_eq!(
+            Ok(Action::RemoveRoot(home_dir().join("code"))),
+            parse("root rm ~/code")
+        );
+        assert_eq!(Ok(Action::Delete), parse(" delete "));
+        assert_eq!(
+            Ok(Action::ExportSelection(home_dir().join("plan.txt"))),
+            parse("export ~/plan.txt")
+        );
+        assert!(parse("import").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_unknown_input() {
+        assert!(parse("explode").is_err());
+        assert!(parse("select size>lots").is_err());
+        assert!(parse("sort size sideways").is_err());
+        assert!(parse("tag two words").is_err());
+        assert!(parse("root add").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn is_git_keeps_projects_inside_a_repository() {
+        let root = std::env::temp_dir().join("nm-finder-rs-git-filter");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("repo/.git")).unwrap();
+        fs::create_dir_all(root.join("repo/packages/ui")).unwrap();
+        fs::create_dir_all(root.join("scratch")).unwrap();
+        let data = |project: &str| Data {
+            project: root.join(project),
+            ..Default::default()
+        };
+        assert!(filter_matches(
+            "is:git",
+            &data("repo/packages/ui"),
+            None,
+            false
+        ));
+        assert!(!filter_matches("is:git", &data("scratch"), None, false));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
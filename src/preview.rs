@@ -0,0 +1,167 @@
+use rayon::prelude::*;
+use std::{fs, path::Path};
+
+use crate::format::format_count;
+use crate::json::{self, Value};
+use crate::scanner::folder_stats;
+
+const LARGEST_PACKAGES: usize = 8;
+const PACKAGE_JSON_LINES: usize = 12;
+
+#[derive(Debug, Clone, Default)]
+pub struct Preview {
+    pub largest_packages: Vec<(String, u64)>,
+    pub package: Option<PackageInfo>,
+    // The first lines of a package.json that didn't parse, shown as they are.
+    pub package_json: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageInfo {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub scripts: usize,
+}
+
+impl PackageInfo {
+    pub fn parse(manifest: &str) -> Option<Self> {
+        let manifest = json::parse(manifest)?;
+        let field = |key: &str| {
+            manifest
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let scripts = match manifest.get("scripts") {
+            Some(Value::Object(scripts)) => scripts.len(),
+            _ => 0,
+        };
+        Some(Self {
+            name: field("name"),
+            version: field("version"),
+            description: field("description"),
+            scripts,
+        })
+    }
+}
+
+impl Preview {
+    pub fn load(artifact: &Path, project: &Path) -> Self {
+        let manifest = fs::read_to_string(project.join("package.json")).ok();
+        let package = manifest.as_deref().and_then(PackageInfo::parse);
+        let package_json = match (&manifest, &package) {
+            (Some(contents), None) => contents
+                .lines()
+                .take(PACKAGE_JSON_LINES)
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let mut largest_packages = package_sizes(artifact);
+        largest_packages.truncate(LARGEST_PACKAGES);
+
+        Self {
+            largest_packages,
+            package,
+            package_json,
+        }
+    }
+
+    // The parsed fields when package.json is valid, otherwise its first lines.
+    pub fn package_lines(&self) -> Vec<String> {
+        let Some(package) = &self.package else {
+            return self.package_json.clone();
+        };
+        [
+            ("Name", package.name.clone()),
+            ("Version", package.version.clone()),
+            ("About", package.description.clone()),
+            ("Scripts", Some(format_count(package.scripts as u64))),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| Some(format!("{:<10}{}", format!("{label}:"), value?)))
+        .collect()
+    }
+}
+
+pub fn package_sizes(node_modules: &Path) -> Vec<(String, u64)> {
+    let mut packages: Vec<(String, u64)> = package_dirs(node_modules)
+        .into_par_iter()
+        .filter_map(|(name, path)| folder_stats(&path).ok().map(|s| (name, s.bytes)))
+        .collect();
+    packages.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    packages
+}
+
+pub fn package_dirs(node_modules: &Path) -> Vec<(String, std::path::PathBuf)> {
+    let virtual_store = node_modules.join(".pnpm");
+    if virtual_store.is_dir() {
+        return fs::read_dir(virtual_store)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                    .map(|e| (e.file_name().to_string_lossy().to_string(), e.path()))
+                    .filter(|(name, _)| name != "node_modules")
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    let Ok(entries) = fs::read_dir(node_modules) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .flat_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('@') {
+                package_dirs(&entry.path())
+                    .into_iter()
+                    .map(|(inner, path)| (format!("{name}/{inner}"), path))
+                    .collect()
+            } else if name.starts_with('.') {
+                Vec::new()
+            } else {
+                vec![(name, entry.path())]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PackageInfo, Preview};
+
+    #[test]
+    fn package_info_reads_the_key_fields() {
+        let manifest = r#"{
+            "name": "@acme/web",
+            "version": "2.1.0",
+            "scripts": { "dev": "vite", "build": "vite build", "test": "vitest" }
+        }"#;
+        assert_eq!(
+            Some(PackageInfo {
+                name: Some("@acme/web".to_string()),
+                version: Some("2.1.0".to_string()),
+                description: None,
+                scripts: 3,
+            }),
+            PackageInfo::parse(manifest)
+        );
+        assert_eq!(None, PackageInfo::parse("{ \"name\": "));
+
+        let preview = Preview {
+            package: PackageInfo::parse(manifest),
+            ..Preview::default()
+        };
+        assert_eq!(
+            vec!["Name:     @acme/web", "Version:  2.1.0", "Scripts:  3"],
+            preview.package_lines()
+        );
+    }
+}
@@ -0,0 +1,29 @@
+// Used as the start handler of scan and sizing workers with `--background`, so
+// only those threads yield to builds; the UI thread keeps its normal priority.
+#[cfg(target_os = "linux")]
+pub fn lower_current_thread() {
+    // With `who` 0 both calls apply to the calling thread only.
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, 19);
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+        );
+    }
+}
+
+// Background QoS lowers both CPU and disk priority on macOS.
+#[cfg(target_os = "macos")]
+pub fn lower_current_thread() {
+    unsafe {
+        libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, libc::PRIO_DARWIN_BG);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn lower_current_thread() {}
@@ -0,0 +1,131 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::format::{format_bytes, format_count};
+
+const REFRESH: Duration = Duration::from_millis(250);
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+// Shared between the sizing workers, which record finished directories, and a
+// reporter thread that redraws a single status line on stderr.
+pub struct Progress {
+    total: usize,
+    sized: AtomicUsize,
+    failed: AtomicUsize,
+    bytes: AtomicU64,
+    done: AtomicBool,
+    samples: Mutex<VecDeque<(Instant, usize, u64)>>,
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            sized: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            done: AtomicBool::new(false),
+            samples: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn record(&self, bytes: u64) {
+        self.sized.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn record_failure(&self) {
+        self.record(0);
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    pub fn finish(&self) {
+        self.done.store(true, Ordering::Relaxed);
+    }
+
+    pub fn report_until_finished(&self) {
+        let mut stderr = io::stderr();
+        while !self.done.load(Ordering::Relaxed) {
+            thread::sleep(REFRESH);
+            let _ = write!(stderr, "\r{}\x1b[K", self.line());
+            let _ = stderr.flush();
+        }
+        let _ = writeln!(stderr);
+    }
+
+    fn line(&self) -> String {
+        let now = Instant::now();
+        let sized = self.sized.load(Ordering::Relaxed);
+        let bytes = self.bytes.load(Ordering::Relaxed);
+        let mut samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+        samples.push_back((now, sized, bytes));
+        while samples
+            .front()
+            .is_some_and(|(at, ..)| now.duration_since(*at) > RATE_WINDOW)
+        {
+            samples.pop_front();
+        }
+        let (since, sized_then, bytes_then) = samples[0];
+        let elapsed = now.duration_since(since).as_secs_f64();
+        let (dirs_per_sec, bytes_per_sec) = if elapsed > 0.0 {
+            (
+                (sized - sized_then) as f64 / elapsed,
+                (bytes - bytes_then) as f64 / elapsed,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        format_progress(sized, self.total, dirs_per_sec, bytes_per_sec)
+    }
+}
+
+pub fn format_progress(
+    sized: usize,
+    total: usize,
+    dirs_per_sec: f64,
+    bytes_per_sec: f64,
+) -> String {
+    let remaining = total.saturating_sub(sized);
+    let eta = if remaining == 0 {
+        "done".to_string()
+    } else if dirs_per_sec > 0.0 {
+        format!("~{:.0}s remaining", remaining as f64 / dirs_per_sec)
+    } else {
+        "estimating".to_string()
+    };
+    format!(
+        "{}/{} directories sized, {eta}, {}/s",
+        format_count(sized as u64),
+        format_count(total as u64),
+        format_bytes(bytes_per_sec as u64)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_progress;
+
+    #[test]
+    fn format_progress_estimates_from_the_rate() {
+        assert_eq!(
+            "231/540 directories sized, ~45s remaining, 171.7 MiB/s",
+            format_progress(231, 540, 309.0 / 45.0, 180_000_000.0)
+        );
+        assert_eq!(
+            "0/10 directories sized, estimating, 0 B/s",
+            format_progress(0, 10, 0.0, 0.0)
+        );
+    }
+}
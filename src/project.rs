@@ -0,0 +1,129 @@
+use std::{fs, path::Path};
+
+use crate::json::{self, Value};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ProjectType {
+    NextJs,
+    Vite,
+    ReactNative,
+    Electron,
+    Node,
+    Rust,
+    Python,
+    #[default]
+    Unknown,
+}
+
+// Any of these next to node_modules means a project still owns it.
+const MANIFESTS: [&str; 7] = [
+    "package.json",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lock",
+    "bun.lockb",
+];
+
+// Left behind by a project that was moved or deleted.
+pub fn is_orphan(project: &Path) -> bool {
+    !MANIFESTS.iter().any(|file| project.join(file).exists())
+}
+
+impl ProjectType {
+    // Stable identifier for `list --json`; unlike the badge it never changes wording.
+    pub const fn name(self) -> &'static str {
+        match self {
+            ProjectType::NextJs => "next_js",
+            ProjectType::Vite => "vite",
+            ProjectType::ReactNative => "react_native",
+            ProjectType::Electron => "electron",
+            ProjectType::Node => "node",
+            ProjectType::Rust => "rust",
+            ProjectType::Python => "python",
+            ProjectType::Unknown => "unknown",
+        }
+    }
+
+    pub const fn badge(self) -> &'static str {
+        match self {
+            ProjectType::NextJs => "Next.js",
+            ProjectType::Vite => "Vite",
+            ProjectType::ReactNative => "React Native",
+            ProjectType::Electron => "Electron",
+            ProjectType::Node => "Node",
+            ProjectType::Rust => "Rust",
+            ProjectType::Python => "Python",
+            ProjectType::Unknown => "",
+        }
+    }
+
+    pub fn detect(project: &Path) -> Self {
+        if let Some(manifest) = fs::read_to_string(project.join("package.json"))
+            .ok()
+            .and_then(|contents| json::parse(&contents))
+        {
+            return Self::from_package_json(&manifest);
+        }
+        if project.join("Cargo.toml").is_file() {
+            return ProjectType::Rust;
+        }
+        if ["pyproject.toml", "requirements.txt", "setup.py"]
+            .iter()
+            .any(|file| project.join(file).is_file())
+        {
+            return ProjectType::Python;
+        }
+        ProjectType::Unknown
+    }
+
+    fn from_package_json(manifest: &Value) -> Self {
+        let depends_on = |package: &str| {
+            ["dependencies", "devDependencies"]
+                .iter()
+                .filter_map(|section| manifest.get(section))
+                .any(|deps| deps.get(package).is_some())
+        };
+
+        // Ordered so the most specific framework wins, e.g. Electron apps built with Vite.
+        if depends_on("electron") {
+            ProjectType::Electron
+        } else if depends_on("react-native") {
+            ProjectType::ReactNative
+        } else if depends_on("next") {
+            ProjectType::NextJs
+        } else if depends_on("vite") {
+            ProjectType::Vite
+        } else {
+            ProjectType::Node
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProjectType;
+    use crate::json;
+
+    #[test]
+    fn from_package_json_prefers_the_most_specific_framework() {
+        let manifest = json::parse(
+            r#"{ "dependencies": { "react": "18" }, "devDependencies": { "vite": "5", "electron": "30" } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ProjectType::Electron,
+            ProjectType::from_package_json(&manifest)
+        );
+
+        let manifest = json::parse(r#"{ "dependencies": { "next": "14" } }"#).unwrap();
+        assert_eq!(
+            ProjectType::NextJs,
+            ProjectType::from_package_json(&manifest)
+        );
+
+        let manifest = json::parse(r#"{ "name": "plain" }"#).unwrap();
+        assert_eq!(ProjectType::Node, ProjectType::from_package_json(&manifest));
+    }
+}
@@ -0,0 +1,488 @@
+use rayon::prelude::*;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::error::CleanerError;
+use crate::format::{format_bytes, format_count};
+use crate::guard::check_target;
+use crate::hook::{self, Hooks};
+
+#[cfg(target_os = "linux")]
+pub use dirfd::remove_tree;
+#[cfg(not(target_os = "linux"))]
+pub use portable::remove_tree;
+
+// Entries above this depth are removed in parallel so a node_modules with
+// thousands of packages isn't unlinked one by one; each deeper subtree is
+// removed by the thread that reached it.
+const PARALLEL_DEPTH: usize = 2;
+
+#[cfg(not(target_os = "linux"))]
+mod portable {
+    use rayon::prelude::*;
+    use std::{fs, io, path::Path};
+
+    use super::PARALLEL_DEPTH;
+
+    pub fn remove_tree(path: &Path) -> io::Result<()> {
+        remove_level(path, 0)
+    }
+
+    fn remove_level(path: &Path, depth: usize) -> io::Result<()> {
+        let meta = fs::symlink_metadata(path)?;
+        if !meta.is_dir() {
+            // Never follow symlinks out of the tree being deleted.
+            return fs::remove_file(path).or_else(|_| fs::remove_dir(path));
+        }
+        if depth >= PARALLEL_DEPTH {
+            return fs::remove_dir_all(path);
+        }
+
+        let entries: Vec<_> = fs::read_dir(path)?.collect::<io::Result<_>>()?;
+        let failures: Vec<io::Error> = entries
+            .par_iter()
+            .filter_map(|entry| remove_level(&entry.path(), depth + 1).err())
+            .collect();
+        if let Some(error) = failures.into_iter().next() {
+            return Err(error);
+        }
+        fs::remove_dir(path)
+    }
+}
+
+// Walks with directory fds and unlinkat, so the kernel never resolves a full path
+// per file and entries are classified from readdir's d_type without a stat.
+#[cfg(target_os = "linux")]
+mod dirfd {
+    use rayon::prelude::*;
+    use std::{
+        ffi::{CStr, CString},
+        fs, io,
+        mem::MaybeUninit,
+        os::{
+            fd::{AsRawFd, FromRawFd, OwnedFd},
+            unix::ffi::OsStrExt,
+        },
+        path::Path,
+    };
+
+    use super::PARALLEL_DEPTH;
+
+    pub fn remove_tree(path: &Path) -> io::Result<()> {
+        if !fs::symlink_metadata(path)?.is_dir() {
+            // Never follow symlinks out of the tree being deleted.
+            return fs::remove_file(path);
+        }
+        let name = CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+        let dir = open_dir(libc::AT_FDCWD, &name)?;
+        remove_contents(&dir, 0)?;
+        drop(dir);
+        fs::remove_dir(path)
+    }
+
+    fn remove_contents(dir: &OwnedFd, depth: usize) -> io::Result<()> {
+        let entries = read_entries(dir)?;
+        let remove = |(name, is_dir): &(CString, bool)| {
+            if *is_dir {
+                let child = open_dir(dir.as_raw_fd(), name)?;
+                remove_contents(&child, depth + 1)?;
+                drop(child);
+                unlink_at(dir, name, libc::AT_REMOVEDIR)
+            } else {
+                unlink_at(dir, name, 0)
+            }
+        };
+        if depth < PARALLEL_DEPTH {
+            let failures: Vec<io::Error> = entries
+                .par_iter()
+                .filter_map(|entry| remove(entry).err())
+                .collect();
+            failures.into_iter().next().map_or(Ok(()), Err)
+        } else {
+            entries.iter().try_for_each(remove)
+        }
+    }
+
+    // O_NOFOLLOW: a directory swapped for a symlink mid-walk fails instead of
+    // being followed.
+    fn open_dir(parent: libc::c_int, name: &CStr) -> io::Result<OwnedFd> {
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+        let fd = unsafe { libc::openat(parent, name.as_ptr(), flags) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    fn unlink_at(dir: &OwnedFd, name: &CStr, flags: libc::c_int) -> io::Result<()> {
+        if unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), flags) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Names with whether each is a real directory; symlinks count as files.
+    fn read_entries(dir: &OwnedFd) -> io::Result<Vec<(CString, bool)>> {
+        // fdopendir takes ownership of the fd it's given, so it gets a duplicate.
+        let fd = unsafe { libc::fcntl(dir.as_raw_fd(), libc::F_DUPFD_CLOEXEC, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let stream = unsafe { libc::fdopendir(fd) };
+        if stream.is_null() {
+            let error = io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(error);
+        }
+        let mut entries = Vec::new();
+        let result = loop {
+            unsafe { *libc::__errno_location() = 0 };
+            let entry = unsafe { libc::readdir(stream) };
+            if entry.is_null() {
+                let error = io::Error::last_os_error();
+                break match error.raw_os_error() {
+                    Some(0) => Ok(entries),
+                    _ => Err(error),
+                };
+            }
+            let (name, kind) =
+                unsafe { (CStr::from_ptr((*entry).d_name.as_ptr()), (*entry).d_type) };
+            if name == c"." || name == c".." {
+                continue;
+            }
+            let is_dir = match kind {
+                libc::DT_DIR => true,
+                libc::DT_UNKNOWN => is_dir_at(dir, name)?,
+                _ => false,
+            };
+            entries.push((name.to_owned(), is_dir));
+        };
+        unsafe { libc::closedir(stream) };
+        result
+    }
+
+    // Some filesystems don't fill in d_type.
+    fn is_dir_at(dir: &OwnedFd, name: &CStr) -> io::Result<bool> {
+        let mut stat = MaybeUninit::<libc::stat>::uninit();
+        let flags = libc::AT_SYMLINK_NOFOLLOW;
+        if unsafe { libc::fstatat(dir.as_raw_fd(), name.as_ptr(), stat.as_mut_ptr(), flags) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mode = unsafe { stat.assume_init() }.st_mode;
+        Ok(mode & libc::S_IFMT == libc::S_IFDIR)
+    }
+}
+
+// With a `delete_command` configured that runs instead, and its output is returned.
+pub fn delete(path: &Path, command: Option<&str>) -> Result<String, CleanerError> {
+    let result = match command {
+        Some(command) => hook::run(command, path),
+        None => remove_tree(path).map(|()| String::new()),
+    };
+    result.map_err(|source| CleanerError::Delete {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+// The pre-delete hook, the artifact and anything nested in it, then the
+// post-delete hook. A failing pre-hook leaves the entry untouched; a failing
+// post-hook is only reported, the directory is already gone. Nothing runs for a
+// path that fails `check_target` against `roots`.
+pub fn delete_entry(
+    path: &Path,
+    nested: &[PathBuf],
+    hooks: &Hooks,
+    roots: &[PathBuf],
+) -> Result<String, CleanerError> {
+    for path in std::iter::once(path).chain(nested.iter().map(PathBuf::as_path)) {
+        check_target(path, roots).map_err(|source| CleanerError::Delete {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    }
+    let mut output = Vec::new();
+    if let Some(pre) = &hooks.pre_delete {
+        let text = hook::run(pre, path).map_err(|error| CleanerError::Delete {
+            path: path.to_path_buf(),
+            source: io::Error::other(format!("pre-delete hook failed: {error}")),
+        })?;
+        output.push(text);
+    }
+    for path in std::iter::once(path).chain(nested.iter().map(PathBuf::as_path)) {
+        output.push(delete(path, hooks.delete.as_deref())?);
+    }
+    if let Some(post) = &hooks.post_delete {
+        output.push(
+            hook::run(post, path)
+                .unwrap_or_else(|error| format!("post-delete hook failed: {error}")),
+        );
+    }
+    output.retain(|text| !text.is_empty());
+    Ok(output.join("\n"))
+}
+
+pub struct Target {
+    pub path: PathBuf,
+    pub nested: Vec<PathBuf>,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+pub enum DeletionEvent {
+    Started(PathBuf),
+    // With the delete command's output, empty for the built-in removal.
+    Finished(PathBuf, Result<String, CleanerError>),
+}
+
+// A batch running on a background thread; the UI drains row updates with `poll`.
+pub struct Deletion {
+    // Each event with the bytes and files freed if it finished successfully.
+    receiver: Receiver<(DeletionEvent, u64, u64)>,
+    started: Instant,
+    pub total: usize,
+    pub finished: usize,
+    pub failed: usize,
+    pub files: u64,
+    pub bytes: u64,
+    // The last line printed by the delete command, if one is configured.
+    pub output: Option<String>,
+}
+
+impl Deletion {
+    pub fn start(targets: Vec<Target>, hooks: Hooks, roots: Vec<PathBuf>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let total = targets.len();
+        thread::spawn(move || {
+            // If the batch hook fails, every entry fails with it and nothing is removed.
+            if let Some(Err(error)) = hooks.pre_batch.as_deref().map(hook::run_batch) {
+                for target in targets {
+                    let source = io::Error::other(format!("pre-batch hook failed: {error}"));
+                    let result = Err(CleanerError::Delete {
+                        path: target.path.clone(),
+                        source,
+                    });
+                    let _ = sender.send((DeletionEvent::Finished(target.path, result), 0, 0));
+                }
+                return;
+            }
+            targets
+                .into_par_iter()
+                .for_each_with(sender, |sender, target| {
+                    let _ = sender.send((DeletionEvent::Started(target.path.clone()), 0, 0));
+                    let result = delete_entry(&target.path, &target.nested, &hooks, &roots);
+                    let finished = DeletionEvent::Finished(target.path, result);
+                    let _ = sender.send((finished, target.bytes, target.files));
+                });
+            if let Some(post) = &hooks.post_batch {
+                let _ = hook::run_batch(post);
+            }
+        });
+        Self::receiving(receiver, total)
+    }
+
+    // Reports every target as removed, one per `pause`, without touching the disk.
+    pub fn simulate(targets: Vec<Target>, pause: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let total = targets.len();
+        thread::spawn(move || {
+            for target in targets {
+                let _ = sender.send((DeletionEvent::Started(target.path.clone()), 0, 0));
+                thread::sleep(pause);
+                let finished = DeletionEvent::Finished(target.path, Ok(String::new()));
+                let _ = sender.send((finished, target.bytes, target.files));
+            }
+        });
+        Self::receiving(receiver, total)
+    }
+
+    fn receiving(receiver: Receiver<(DeletionEvent, u64, u64)>, total: usize) -> Self {
+        Self {
+            receiver,
+            started: Instant::now(),
+            total,
+            finished: 0,
+            failed: 0,
+            files: 0,
+            bytes: 0,
+            output: None,
+        }
+    }
+
+    pub fn poll(&mut self) -> Vec<DeletionEvent> {
+        let mut events = Vec::new();
+        while let Ok((event, bytes, files)) = self.receiver.try_recv() {
+            if let DeletionEvent::Finished(_, result) = &event {
+                self.finished += 1;
+                // A failed target may be partly removed, but only whole targets are counted.
+                match result {
+                    Ok(output) => {
+                        self.bytes += bytes;
+                        self.files += files;
+                        if let Some(line) = output.lines().last() {
+                            self.output = Some(line.to_string());
+                        }
+                    }
+                    Err(_) => self.failed += 1,
+                }
+            }
+            events.push(event);
+        }
+        events
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished == self.total
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn status_line(&self) -> String {
+        let failed = match self.failed {
+            0 => String::new(),
+            n => format!(" ({n} failed)"),
+        };
+        format!(
+            "Deleting {}/{} directories{failed} | {}",
+            self.finished,
+            self.total,
+            throughput(self.files, self.bytes, self.elapsed())
+        )
+    }
+}
+
+pub fn throughput(files: u64, bytes: u64, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+    format!(
+        "{} files/s, {}/s",
+        format_count((files as f64 / seconds).round() as u64),
+        format_bytes((bytes as f64 / seconds) as u64)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Deletion, Target, delete_entry, remove_tree, throughput};
+    use crate::hook::Hooks;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn remove_tree_deletes_nested_packages_but_not_symlink_targets() {
+        let root = std::env::temp_dir().join("nm-finder-rs-remove");
+        let _ = fs::remove_dir_all(&root);
+        let outside = root.join("outside");
+        let modules = root.join("node_modules");
+        fs::create_dir_all(modules.join("@scope/pkg/lib")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(modules.join("@scope/pkg/lib/index.js"), "x").unwrap();
+        fs::write(modules.join(".package-lock.json"), "{}").unwrap();
+        fs::write(outside.join("keep.txt"), "keep").unwrap();
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, modules.join("linked")).unwrap();
+            std::os::unix::fs::symlink(&outside, modules.join("@scope/pkg/lib/linked")).unwrap();
+        }
+
+        remove_tree(&modules).unwrap();
+        assert!(!modules.exists());
+        assert!(outside.join("keep.txt").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn failed_target_does_not_stop_the_batch() {
+        let root = std::env::temp_dir().join("nm-finder-rs-remove-batch");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("ok/node_modules")).unwrap();
+        let target = |name: &str, bytes| Target {
+            path: root.join(name).join("node_modules"),
+            nested: Vec::new(),
+            bytes,
+            files: 1,
+        };
+
+        let mut deletion = Deletion::start(
+            vec![target("missing", 10), target("ok", 5)],
+            Hooks::default(),
+            vec![root.clone()],
+        );
+        while !deletion.is_finished() {
+            deletion.poll();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(1, deletion.failed);
+        assert_eq!(5, deletion.bytes);
+        assert!(!root.join("ok/node_modules").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_pre_delete_hook_keeps_the_entry() {
+        let modules = std::env::temp_dir().join("nm-finder-rs-remove-hooks/node_modules");
+        fs::create_dir_all(&modules).unwrap();
+        let mut hooks = Hooks {
+            pre_delete: Some("exit 1".to_string()),
+            post_delete: Some("echo removed {path}".to_string()),
+            ..Hooks::default()
+        };
+
+        let roots = [modules.parent().unwrap().to_path_buf()];
+        assert!(delete_entry(&modules, &[], &hooks, &roots).is_err());
+        assert!(modules.exists());
+        hooks.pre_delete = Some("echo stopping".to_string());
+        let output = delete_entry(&modules, &[], &hooks, &roots).unwrap();
+        assert_eq!(format!("stopping\nremoved {}", modules.display()), output);
+        assert!(!modules.exists());
+
+        fs::remove_dir_all(modules.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_symlinks_and_paths_outside_the_roots() {
+        let root = std::env::temp_dir().join("nm-finder-rs-remove-guard");
+        let _ = fs::remove_dir_all(&root);
+        let scanned = root.join("scanned");
+        let outside = root.join("outside/node_modules");
+        fs::create_dir_all(scanned.join("app")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        let roots = [scanned.clone()];
+        let hooks = Hooks::default();
+
+        let link = scanned.join("app/node_modules");
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+        let error = delete_entry(&link, &[], &hooks, &roots).unwrap_err();
+        assert!(error.to_string().contains("symlink"), "{error}");
+
+        // A linked project directory resolves the artifact outside the roots.
+        std::os::unix::fs::symlink(root.join("outside"), scanned.join("linked")).unwrap();
+        let error =
+            delete_entry(&scanned.join("linked/node_modules"), &[], &hooks, &roots).unwrap_err();
+        assert!(
+            error.to_string().contains("outside the scanned roots"),
+            "{error}"
+        );
+        assert!(outside.exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn throughput_reports_files_and_bytes_per_second() {
+        assert_eq!(
+            "5000 files/s, 2.0 MiB/s",
+            throughput(10_000, 4 * 1024 * 1024, Duration::from_secs(2))
+        );
+    }
+}
@@ -1,20 +1,290 @@
-use homedir::my_home;
-use jwalk::WalkDir;
+use jwalk::{DirEntry, Parallelism, WalkDir};
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder, prelude::*};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-pub fn get_array() -> Vec<String> {
-    let homedir = my_home().unwrap().unwrap();
-    println!("Loading...");
+use crate::config::Config;
+use crate::error::CleanerError;
+use crate::events;
+use crate::glob;
+use crate::model::{ArtifactKind, home_dir};
+use crate::mounts::{is_rotational, network_mounts, user_mounts};
+use crate::priority::lower_current_thread;
 
-    WalkDir::new(&homedir)
-        .process_read_dir(|_, _, _, children| {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FolderStats {
+    pub bytes: u64,
+    pub files: u64,
+    pub cloud_files: u64,
+}
+
+impl std::ops::Add for FolderStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            bytes: self.bytes + other.bytes,
+            files: self.files + other.files,
+            cloud_files: self.cloud_files + other.cloud_files,
+        }
+    }
+}
+
+const HDD_SIZE_THREADS: usize = 2;
+const VISIBLE_HIDDEN_DIRS: [&str; 2] = [".yarn", ".nx"];
+const ARTIFACT_DIR_NAMES: [&str; 4] = ["node_modules", "cache", "unplugged", ".turbo"];
+const HOME_PARENTS: [&str; 2] = ["/home", "/Users"];
+// Sync clients re-upload (or re-download) every file under these when node_modules
+// is deleted or reinstalled. On macOS they live under ~/Library/CloudStorage, which
+// is skipped anyway.
+const CLOUD_SYNC_DIRS: [&str; 5] = [
+    "Dropbox",
+    "OneDrive",
+    "Google Drive",
+    "GoogleDrive",
+    "My Drive",
+];
+
+pub struct Discovery {
+    pub artifacts: Vec<PathBuf>,
+    pub dirs_visited: usize,
+}
+
+// Seeking between many directories at once thrashes spinning disks, so unless a
+// thread count is configured, sizing on an HDD is limited to a couple of workers.
+pub fn size_pool(threads: Option<usize>, root: &Path, background: bool) -> ThreadPool {
+    let threads = threads.or_else(|| is_rotational(root).then_some(HDD_SIZE_THREADS));
+    worker_pool(threads.unwrap_or(0), background).expect("failed to start the sizing thread pool")
+}
+
+fn worker_pool(threads: usize, background: bool) -> Result<ThreadPool, ThreadPoolBuildError> {
+    let builder = ThreadPoolBuilder::new().num_threads(threads);
+    if background {
+        builder.start_handler(|_| lower_current_thread()).build()
+    } else {
+        builder.build()
+    }
+}
+
+// The configured root (or every home with `all_users`) plus any extra volumes, minus
+// roots on network filesystems.
+pub fn scan_roots(config: &Config) -> Vec<PathBuf> {
+    let mut roots = if config.all_users {
+        user_homes()
+    } else {
+        vec![config.root().to_path_buf()]
+    };
+    if config.all_mounts {
+        roots.extend(user_mounts());
+    }
+    let network = skipped_network_mounts(config);
+    roots.retain(|root| !network.iter().any(|mount| root.starts_with(mount)));
+    roots
+}
+
+// Homes the current user can't list are left out rather than failing the scan.
+pub fn user_homes() -> Vec<PathBuf> {
+    HOME_PARENTS
+        .iter()
+        .filter_map(|parent| fs::read_dir(parent).ok())
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default();
+            path.is_dir() && name != "Shared" && name != "Guest" && name != "lost+found"
+        })
+        .filter(|path| fs::read_dir(path).is_ok())
+        .collect()
+}
+
+pub fn home_owner(path: &Path) -> Option<String> {
+    let relative = HOME_PARENTS
+        .iter()
+        .find_map(|parent| path.strip_prefix(parent).ok())?;
+    let user = relative.components().next()?;
+    Some(user.as_os_str().to_string_lossy().into_owned())
+}
+
+// Also matches "Dropbox (Personal)" and "OneDrive - Contoso" style business folders.
+// Only meaningful for a directory right inside a home, see `is_home`.
+pub fn is_cloud_sync_dir(name: &str) -> bool {
+    CLOUD_SYNC_DIRS.iter().any(|dir| {
+        name.strip_prefix(dir).is_some_and(|rest| {
+            rest.is_empty() || rest.starts_with(" (") || rest.starts_with(" - ")
+        })
+    })
+}
+
+pub fn in_cloud_sync(path: &Path) -> bool {
+    path.ancestors().any(|dir| {
+        dir.parent().is_some_and(is_home)
+            && dir
+                .file_name()
+                .is_some_and(|name| is_cloud_sync_dir(&name.to_string_lossy()))
+    })
+}
+
+// The current user's home, or any /home/<user> or /Users/<user>.
+fn is_home(dir: &Path) -> bool {
+    dir == home_dir()
+        || dir
+            .parent()
+            .is_some_and(|parent| HOME_PARENTS.iter().any(|home| parent == Path::new(home)))
+}
+
+pub fn skipped_network_mounts(config: &Config) -> Vec<PathBuf> {
+    if config.scan_network {
+        return Vec::new();
+    }
+    let root = config.root();
+    network_mounts()
+        .into_iter()
+        .filter(|mount| config.all_mounts || mount.starts_with(root) || root.starts_with(mount))
+        .collect()
+}
+
+// Only an unreadable root is an error; unreadable directories below it are skipped.
+pub fn get_array(config: &Config) -> Result<Vec<PathBuf>, CleanerError> {
+    let root = config.root();
+    if !config.all_users
+        && let Err(source) = fs::read_dir(root)
+    {
+        return Err(CleanerError::Scan {
+            path: root.to_path_buf(),
+            source,
+        });
+    }
+    let roots = scan_roots(config);
+    if config.progress_json {
+        events::scan_started(&roots);
+    } else {
+        eprintln!("Loading...");
+    }
+
+    Ok(roots
+        .into_iter()
+        .flat_map(|root| walk_dirs(&root, config))
+        .filter_map(|entry| artifact_path(&entry))
+        .chain(listed_global_caches(config))
+        .inspect(|path| {
+            if config.progress_json {
+                events::dir_found(path);
+            }
+        })
+        .collect())
+}
+
+// Global caches live outside most roots, so a custom root only picks up the ones
+// inside it; scanning home lists them wherever DENO_DIR or BUN_INSTALL point.
+pub fn listed_global_caches(config: &Config) -> Vec<PathBuf> {
+    let root = config.root();
+    let exclude = glob::compile(&config.exclude);
+    ArtifactKind::global_caches()
+        .into_iter()
+        .filter(|(kind, _)| config.scans(*kind))
+        .map(|(_, path)| path)
+        .filter(|path| path.is_dir() && (root == home_dir() || path.starts_with(root)))
+        .filter(|path| !glob::excluded(&exclude, path))
+        .collect()
+}
+
+// What `guard::check_target` accepts deleting under: the roots plus the global
+// caches a scan lists, which can be outside all of them.
+pub fn deletion_roots(roots: &[PathBuf], config: &Config) -> Vec<PathBuf> {
+    roots
+        .iter()
+        .cloned()
+        .chain(listed_global_caches(config))
+        .collect()
+}
+
+pub fn discover(root: &Path, config: &Config) -> Discovery {
+    let mut dirs_visited = 0;
+    let artifacts = walk_dirs(root, config)
+        .inspect(|_| dirs_visited += 1)
+        .filter_map(|entry| artifact_path(&entry))
+        .collect();
+
+    Discovery {
+        artifacts,
+        dirs_visited,
+    }
+}
+
+// Streams every directory that survives pruning; nothing is buffered beyond what jwalk
+// keeps in flight, so memory stays flat no matter how large the tree is.
+fn walk_dirs(root: &Path, config: &Config) -> impl Iterator<Item = DirEntry<((), ())>> + use<> {
+    let parallelism = match config.scan_threads {
+        // Even a single scan thread gets its own pool, so the caller keeps its priority.
+        threads if config.background => match worker_pool(threads.unwrap_or(0), true) {
+            Ok(pool) => Parallelism::RayonExistingPool {
+                pool: Arc::new(pool),
+                busy_timeout: Some(Duration::from_secs(1)),
+            },
+            Err(_) => Parallelism::Serial,
+        },
+        Some(1) => Parallelism::Serial,
+        Some(threads) => Parallelism::RayonNewPool(threads),
+        None => Parallelism::RayonDefaultPool {
+            busy_timeout: Duration::from_secs(1),
+        },
+    };
+    let skip_dirs = config.skip_dirs();
+    let home_skip_dirs = config.home_skip_dirs();
+    let exclude = glob::compile(&config.exclude);
+    let include_hidden = config.include_hidden;
+    let scan_cloud_sync = config.scan_cloud_sync;
+    let network = skipped_network_mounts(config);
+    WalkDir::new(root)
+        .skip_hidden(false)
+        .parallelism(parallelism)
+        .process_read_dir(move |_, parent, _, children| {
+            let in_yarn_dir = parent.ends_with(".yarn");
+            let in_nx_dir = parent.file_name().is_some_and(|name| name == ".nx");
+            let in_home = is_home(parent);
+            // Excluded directories are dropped outright, so neither they nor anything
+            // below them is walked or listed.
+            children.retain(|r| {
+                r.as_ref().is_ok_and(|entry| {
+                    entry.file_type().is_dir()
+                        && !glob::excluded(&exclude, &parent.join(entry.file_name()))
+                })
+            });
             children.iter_mut().for_each(|r| {
-                if let Ok(entry) = r
-                    && entry.file_type().is_dir()
-                {
+                if let Ok(entry) = r {
+                    if network.iter().any(|mount| {
+                        mount.parent() == Some(parent)
+                            && mount.file_name() == Some(entry.file_name())
+                    }) {
+                        entry.read_children_path = None;
+                        return;
+                    }
                     let name = entry.file_name().to_string_lossy();
                     match name.as_ref() {
-                        "node_modules" | ".cache" | ".vscode" | ".local" | ".npm" | ".nvm"
-                        | ".steam" | ".var" | ".cargo" | "caches" | "Caches" => {
+                        "node_modules" | ".turbo" => entry.read_children_path = None,
+                        "cache" | "unplugged" if in_yarn_dir => {
+                            entry.read_children_path = None;
+                        }
+                        "cache" if in_nx_dir => entry.read_children_path = None,
+                        skipped if skip_dirs.iter().any(|dir| dir == skipped) => {
+                            entry.read_children_path = None;
+                        }
+                        skipped if in_home && home_skip_dirs.iter().any(|dir| dir == skipped) => {
+                            entry.read_children_path = None;
+                        }
+                        synced if in_home && !scan_cloud_sync && is_cloud_sync_dir(synced) => {
+                            entry.read_children_path = None;
+                        }
+                        hidden
+                            if !include_hidden
+                                && hidden.starts_with('.')
+                                && !VISIBLE_HIDDEN_DIRS.contains(&hidden) =>
+                        {
                             entry.read_children_path = None;
                         }
                         _ => {}
@@ -24,15 +294,191 @@ pub fn get_array() -> Vec<String> {
         })
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|entry| entry.file_type().is_dir() && entry.path().ends_with("node_modules"))
-        .map(|entry| {
-            entry
-                .path()
-                .to_str()
-                .unwrap_or("")
-                .to_string()
-                .trim_start_matches(homedir.to_str().unwrap())
-                .to_string()
-        })
-        .collect()
+}
+
+fn artifact_path(entry: &DirEntry<((), ())>) -> Option<PathBuf> {
+    // Checking the name first avoids building a full path for every directory walked.
+    if !ARTIFACT_DIR_NAMES.contains(&entry.file_name().to_str()?) {
+        return None;
+    }
+    let path = entry.path();
+    ArtifactKind::detect_local(&path).map(|_| path)
+}
+
+pub fn folder_stats(path: &Path) -> io::Result<FolderStats> {
+    walk_stats(path, false)
+}
+
+// pnpm hardlinks package files from its global store, so only files without other
+// links are actually freed when the virtual store is deleted.
+pub fn reclaimable_stats(path: &Path) -> io::Result<FolderStats> {
+    walk_stats(path, true)
+}
+
+fn walk_stats(path: &Path, unique_only: bool) -> io::Result<FolderStats> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_file() {
+        if unique_only && link_count(&meta) > 1 {
+            return Ok(FolderStats::default());
+        }
+        // Online-only files take no local space even though they report their full size.
+        if is_cloud_placeholder(path, &meta) {
+            return Ok(FolderStats {
+                bytes: 0,
+                files: 1,
+                cloud_files: 1,
+            });
+        }
+        return Ok(FolderStats {
+            bytes: disk_usage(&meta),
+            files: 1,
+            cloud_files: 0,
+        });
+    }
+    if !meta.is_dir() {
+        return Ok(FolderStats::default());
+    }
+
+    let entries: Vec<_> = fs::read_dir(path)?.collect();
+    Ok(entries
+        .par_iter()
+        .filter_map(|entry| entry.as_ref().ok())
+        .filter_map(|entry| walk_stats(&entry.path(), unique_only).ok())
+        .reduce(FolderStats::default, |a, b| a + b))
+}
+
+#[cfg(unix)]
+fn link_count(meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink()
+}
+
+#[cfg(not(unix))]
+fn link_count(_meta: &fs::Metadata) -> u64 {
+    1
+}
+
+// Allocated blocks rather than the apparent length, so sparse files only count the
+// space they actually occupy (the same figure `du` reports).
+#[cfg(unix)]
+fn disk_usage(meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn disk_usage(meta: &fs::Metadata) -> u64 {
+    meta.len()
+}
+
+// iCloud marks evicted files as dataless; FUSE-based OneDrive/Dropbox clients show
+// them as files with a size but no allocated blocks. Anywhere else that is just a
+// sparse file, so it only counts on FUSE. Tiny files are skipped since some
+// filesystems store them inline without blocks.
+#[cfg(unix)]
+fn is_cloud_placeholder(path: &Path, meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::macos::fs::MetadataExt as _;
+        const SF_DATALESS: u32 = 0x4000_0000;
+        if meta.st_flags() & SF_DATALESS != 0 {
+            return true;
+        }
+    }
+    meta.len() > 4096 && meta.blocks() == 0 && on_fuse(path)
+}
+
+#[cfg(target_os = "linux")]
+fn on_fuse(path: &Path) -> bool {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stat = MaybeUninit::<libc::statfs>::uninit();
+    unsafe {
+        libc::statfs(path.as_ptr(), stat.as_mut_ptr()) == 0
+            && stat.assume_init().f_type == libc::FUSE_SUPER_MAGIC
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn on_fuse(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(windows)]
+fn is_cloud_placeholder(_path: &Path, meta: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const OFFLINE: u32 = 0x1000;
+    const RECALL_ON_OPEN: u32 = 0x4_0000;
+    const RECALL_ON_DATA_ACCESS: u32 = 0x40_0000;
+    meta.file_attributes() & (OFFLINE | RECALL_ON_OPEN | RECALL_ON_DATA_ACCESS) != 0
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_cloud_placeholder(_path: &Path, _meta: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{disk_usage, folder_stats, home_owner, in_cloud_sync, is_cloud_sync_dir};
+    use crate::model::home_dir;
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+
+    #[test]
+    fn folder_stats_counts_allocated_space_of_sparse_files() {
+        let dir = std::env::temp_dir().join("nm-finder-rs-sparse");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut file = fs::File::create(dir.join("image.raw")).unwrap();
+        file.write_all(&[1; 64 * 1024]).unwrap();
+        file.set_len(64 * 1024 * 1024).unwrap();
+        file.sync_all().unwrap();
+
+        // Holes in a local file are just unallocated, not an online-only placeholder.
+        let stats = folder_stats(&dir).unwrap();
+        let meta = fs::metadata(dir.join("image.raw")).unwrap();
+        assert_eq!(1, stats.files);
+        assert_eq!(0, stats.cloud_files);
+        assert_eq!(disk_usage(&meta), stats.bytes);
+        assert!(stats.bytes < 64 * 1024 * 1024);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cloud_sync_dirs_include_business_and_team_variants() {
+        assert!(is_cloud_sync_dir("Dropbox"));
+        assert!(is_cloud_sync_dir("Dropbox (Personal)"));
+        assert!(is_cloud_sync_dir("OneDrive - Contoso"));
+        assert!(is_cloud_sync_dir("Google Drive"));
+        assert!(!is_cloud_sync_dir("dropbox-sdk"));
+        assert!(!is_cloud_sync_dir("OneDriveBackup"));
+        assert!(in_cloud_sync(Path::new(
+            "/home/ana/Dropbox/app/node_modules"
+        )));
+        assert!(!in_cloud_sync(Path::new("/home/ana/code/app/node_modules")));
+        // A project folder that happens to share the name isn't a sync root.
+        assert!(!in_cloud_sync(Path::new(
+            "/home/ana/code/Dropbox/node_modules"
+        )));
+        assert!(in_cloud_sync(&home_dir().join("OneDrive/app/node_modules")));
+    }
+
+    #[test]
+    fn home_owner_takes_the_directory_under_home() {
+        assert_eq!(
+            Some("ana".to_string()),
+            home_owner(Path::new("/home/ana/code/app/node_modules"))
+        );
+        assert_eq!(
+            Some("bo".to_string()),
+            home_owner(Path::new("/Users/bo/node_modules"))
+        );
+        assert_eq!(None, home_owner(Path::new("/srv/app/node_modules")));
+    }
 }
@@ -0,0 +1,156 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufRead, IsTerminal, Write},
+};
+
+use crate::config::config_path;
+use crate::dirs::ensure_dir;
+use crate::model::{PALETTES, home_dir};
+use crate::palette::root_path;
+
+// Runs instead of the built-in removal when the trash is picked.
+const TRASH_COMMAND: Option<&str> = if cfg!(target_os = "macos") {
+    Some("trash {path}")
+} else if cfg!(windows) {
+    None
+} else {
+    Some("trash-put {path}")
+};
+
+// Only on an interactive first launch: no config file yet, none named by
+// NMC_CONFIG, and someone at the terminal to answer.
+pub fn needed() -> bool {
+    env::var_os("NMC_CONFIG").is_none()
+        && !config_path().exists()
+        && io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+}
+
+// Asks on the terminal and writes the config file the app then loads.
+pub fn run() -> io::Result<()> {
+    let path = config_path();
+    let contents = ask(&mut io::stdin().lock(), &mut io::stdout())?;
+    if let Some(dir) = path.parent() {
+        ensure_dir(dir)?;
+    }
+    File::create(&path)?.write_all(contents.as_bytes())?;
+    println!(
+        "Saved to {}, edit it or delete it to start over.",
+        path.display()
+    );
+    Ok(())
+}
+
+// Pressing enter takes the default in brackets, and so does closing the input.
+fn ask(input: &mut impl BufRead, output: &mut impl Write) -> io::Result<String> {
+    writeln!(
+        output,
+        "First launch, a few questions before the first scan.\n"
+    )?;
+    let mut entries = Vec::new();
+
+    let root = prompt(input, output, "Where should scans start?", "~")?;
+    let root = root_path(&root);
+    let root = fs::canonicalize(&root).unwrap_or(root);
+    if root.is_absolute() && root != home_dir() {
+        entries.push(("root", root.to_string_lossy().into_owned()));
+    }
+
+    let caches = confirm(
+        input,
+        output,
+        "Also look for Yarn, Turbo and Nx caches and the Deno and Bun global caches?",
+        true,
+    )?;
+    if !caches {
+        entries.push(("kinds", "node_modules, pnpm".to_string()));
+    }
+
+    if let Some(trash) = TRASH_COMMAND {
+        let question =
+            format!("Move directories to the trash (with `{trash}`) instead of deleting them?");
+        if confirm(input, output, &question, false)? {
+            entries.push(("delete_command", trash.to_string()));
+        }
+    }
+
+    let names: Vec<&str> = PALETTES.iter().map(|(name, _)| *name).collect();
+    let question = format!("Colors: {}?", names.join(", "));
+    let palette = prompt(input, output, &question, names[0])?;
+    if names.iter().any(|name| name.eq_ignore_ascii_case(&palette)) && palette != names[0] {
+        entries.push(("palette", palette.to_lowercase()));
+    }
+
+    let mut contents = String::from("# Written by the first-run setup.\n");
+    for (key, value) in entries {
+        contents.push_str(&format!("{key} = {value}\n"));
+    }
+    Ok(contents)
+}
+
+fn prompt(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: &str,
+) -> io::Result<String> {
+    let answer = read_answer(input, output, &format!("{question} [{default}] "))?;
+    Ok(if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer
+    })
+}
+
+fn confirm(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: bool,
+) -> io::Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    let answer = read_answer(input, output, &format!("{question} [{hint}] "))?;
+    Ok(match answer.to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+fn read_answer(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    prompt: &str,
+) -> io::Result<String> {
+    write!(output, "{prompt}")?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TRASH_COMMAND, ask};
+    use crate::config::Config;
+    use crate::model::ArtifactKind;
+    use std::io::Cursor;
+    use std::path::Path;
+
+    #[test]
+    fn answers_become_config_keys() {
+        let mut output = Vec::new();
+        let contents = ask(&mut Cursor::new("/srv\nn\ny\nindigo\n"), &mut output).unwrap();
+        let config = Config::parse(&contents);
+        assert_eq!(Some(Path::new("/srv")), config.root.as_deref());
+        assert!(!config.scans(ArtifactKind::YarnCache));
+        assert!(config.scans(ArtifactKind::Pnpm));
+        assert_eq!(TRASH_COMMAND.map(str::to_string), config.hooks.delete);
+        assert_eq!(1, config.palette);
+
+        // Enter on every question, or no input at all, keeps the defaults.
+        let contents = ask(&mut Cursor::new(""), &mut output).unwrap();
+        assert_eq!("# Written by the first-run setup.\n", contents);
+    }
+}
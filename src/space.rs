@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+
+use crate::format::format_bytes;
+
+// Free space on every filesystem touched by a deletion batch, taken before the
+// batch runs so the actual gain can be compared with what the sizes promised.
+pub struct SpaceCheck {
+    probes: Vec<(PathBuf, u64)>,
+}
+
+impl SpaceCheck {
+    // `paths` should outlive the deletion, e.g. the parents of the deleted dirs.
+    pub fn before<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Self {
+        let mut devices = Vec::new();
+        let mut probes = Vec::new();
+        for path in paths {
+            let device = device_id(path);
+            if devices.contains(&device) {
+                continue;
+            }
+            if let Some(free) = free_space(path) {
+                devices.push(device);
+                probes.push((path.to_path_buf(), free));
+            }
+        }
+        Self { probes }
+    }
+
+    pub fn reclaimed(&self) -> u64 {
+        self.probes
+            .iter()
+            .filter_map(|(path, before)| Some(free_space(path)?.saturating_sub(*before)))
+            .sum()
+    }
+}
+
+pub fn summary(expected: u64, actual: u64) -> String {
+    let mut summary = format!(
+        "Freed {} on disk (expected {})",
+        format_bytes(actual),
+        format_bytes(expected)
+    );
+    // Some slack for other processes writing while we delete.
+    if actual < expected - expected / 10 {
+        summary.push_str(
+            ", the rest may be held by hardlinks, snapshots or files still open elsewhere",
+        );
+    }
+    summary
+}
+
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> Option<u64> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats = MaybeUninit::<libc::statvfs>::uninit();
+    if unsafe { libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let stats = unsafe { stats.assume_init() };
+    #[allow(clippy::useless_conversion)]
+    Some(u64::from(stats.f_bavail) * u64::from(stats.f_frsize))
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(unix)]
+fn device_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|meta| meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::summary;
+
+    #[test]
+    fn summary_flags_large_shortfalls_only() {
+        assert_eq!("Freed 95 B on disk (expected 100 B)", summary(100, 95));
+        assert!(summary(100, 40).contains("snapshots"));
+    }
+}
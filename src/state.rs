@@ -0,0 +1,275 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::dirs::{cache_dir, ensure_dir, state_dir};
+use crate::model::SortField;
+
+const SELECTION_FILE: &str = "selection";
+const TAGS_FILE: &str = "tags";
+const PRESETS_FILE: &str = "presets";
+const DELETION_LOG: &str = "deletions.log";
+const HISTORY_FILE: &str = "history";
+const VIEW_FILE: &str = "view";
+const BOOKMARKS_FILE: &str = "bookmarks";
+const NOTES_FILE: &str = "notes";
+pub const HISTORY_SAMPLES: usize = 8;
+
+pub type Tags = BTreeMap<PathBuf, BTreeSet<String>>;
+pub type Notes = BTreeMap<PathBuf, String>;
+// The last few (unix-seconds, bytes) samples per directory, oldest first.
+pub type History = BTreeMap<PathBuf, Vec<(u64, u64)>>;
+
+pub fn load_selection() -> Vec<PathBuf> {
+    read_paths(&state_dir().join(SELECTION_FILE))
+}
+
+pub fn save_selection(paths: &[PathBuf]) -> io::Result<()> {
+    let file = state_dir().join(SELECTION_FILE);
+    if paths.is_empty() {
+        return match fs::remove_file(file) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    write_paths(&file, paths)
+}
+
+// One "note<TAB>path" pair per line; notes are kept to a single line without tabs.
+pub fn load_notes() -> Notes {
+    let contents = fs::read_to_string(state_dir().join(NOTES_FILE)).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(note, path)| (PathBuf::from(path), note.to_string()))
+        .collect()
+}
+
+pub fn save_notes(notes: &Notes) -> io::Result<()> {
+    let dir = state_dir();
+    ensure_dir(&dir)?;
+    let mut contents = String::new();
+    for (path, note) in notes {
+        contents.push_str(&format!("{note}\t{}\n", path.to_string_lossy()));
+    }
+    fs::write(dir.join(NOTES_FILE), contents)
+}
+
+pub fn load_bookmarks() -> BTreeSet<PathBuf> {
+    read_paths(&state_dir().join(BOOKMARKS_FILE))
+        .into_iter()
+        .collect()
+}
+
+pub fn save_bookmarks(bookmarks: &BTreeSet<PathBuf>) -> io::Result<()> {
+    let paths: Vec<PathBuf> = bookmarks.iter().cloned().collect();
+    write_paths(&state_dir().join(BOOKMARKS_FILE), &paths)
+}
+
+// One "tag<TAB>path" pair per line.
+pub fn load_tags() -> Tags {
+    let mut tags = Tags::new();
+    let contents = fs::read_to_string(state_dir().join(TAGS_FILE)).unwrap_or_default();
+    for (tag, path) in contents.lines().filter_map(|line| line.split_once('\t')) {
+        tags.entry(PathBuf::from(path))
+            .or_default()
+            .insert(tag.to_string());
+    }
+    tags
+}
+
+pub fn save_tags(tags: &Tags) -> io::Result<()> {
+    let dir = state_dir();
+    ensure_dir(&dir)?;
+    let mut contents = String::new();
+    for (path, names) in tags {
+        for tag in names {
+            contents.push_str(&format!("{tag}\t{}\n", path.to_string_lossy()));
+        }
+    }
+    fs::write(dir.join(TAGS_FILE), contents)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preset {
+    pub name: String,
+    pub filter: String,
+    pub criteria: String,
+}
+
+// One "name<TAB>filter<TAB>criteria" preset per line.
+pub fn load_presets() -> Vec<Preset> {
+    let contents = fs::read_to_string(state_dir().join(PRESETS_FILE)).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some(Preset {
+                name: fields.next()?.to_string(),
+                filter: fields.next()?.to_string(),
+                criteria: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn save_presets(presets: &[Preset]) -> io::Result<()> {
+    let dir = state_dir();
+    ensure_dir(&dir)?;
+    let mut contents = String::new();
+    for preset in presets {
+        contents.push_str(&format!(
+            "{}\t{}\t{}\n",
+            preset.name, preset.filter, preset.criteria
+        ));
+    }
+    fs::write(dir.join(PRESETS_FILE), contents)
+}
+
+// Appends "unix-seconds<TAB>bytes<TAB>path" for every directory removed.
+pub fn log_deletions(deleted: &[(PathBuf, u64)]) -> io::Result<()> {
+    let dir = state_dir();
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(ensure_dir(&dir)?.join(DELETION_LOG))?;
+    let now = unix_now();
+    for (path, bytes) in deleted {
+        writeln!(log, "{now}\t{bytes}\t{}", path.display())?;
+    }
+    Ok(())
+}
+
+// Where the last session left off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct View {
+    pub sort_field: SortField,
+    pub sort_reversed: bool,
+    pub highlighted: Option<PathBuf>,
+}
+
+// "sort<TAB>size desc" and "highlighted<TAB>path" lines.
+pub fn load_view() -> Option<View> {
+    let contents = fs::read_to_string(state_dir().join(VIEW_FILE)).ok()?;
+    let mut view = None;
+    let mut highlighted = None;
+    for (key, value) in contents.lines().filter_map(|line| line.split_once('\t')) {
+        match key {
+            "sort" => {
+                let (field, direction) = value.split_once(' ').unwrap_or((value, "asc"));
+                view = SortField::from_name(field).map(|field| (field, direction == "desc"));
+            }
+            "highlighted" => highlighted = Some(PathBuf::from(value)),
+            _ => {}
+        }
+    }
+    let (sort_field, sort_reversed) = view?;
+    Some(View {
+        sort_field,
+        sort_reversed,
+        highlighted,
+    })
+}
+
+pub fn save_view(view: &View) -> io::Result<()> {
+    let dir = state_dir();
+    ensure_dir(&dir)?;
+    let direction = if view.sort_reversed { "desc" } else { "asc" };
+    let mut contents = format!("sort\t{} {direction}\n", view.sort_field.name());
+    if let Some(path) = &view.highlighted {
+        contents.push_str(&format!("highlighted\t{}\n", path.to_string_lossy()));
+    }
+    fs::write(dir.join(VIEW_FILE), contents)
+}
+
+// One "unix-seconds<TAB>bytes<TAB>path" sample per line. Older versions kept it in
+// the state directory, which is still read until the next scan saves it here.
+pub fn load_history() -> History {
+    let mut history = History::new();
+    let contents = fs::read_to_string(cache_dir().join(HISTORY_FILE))
+        .or_else(|_| fs::read_to_string(state_dir().join(HISTORY_FILE)))
+        .unwrap_or_default();
+    for line in contents.lines() {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(Ok(at)), Some(Ok(bytes)), Some(path)) = (
+            fields.next().map(str::parse),
+            fields.next().map(str::parse),
+            fields.next(),
+        ) else {
+            continue;
+        };
+        history
+            .entry(PathBuf::from(path))
+            .or_default()
+            .push((at, bytes));
+    }
+    history
+}
+
+// Adds this scan's sizes and keeps the newest samples. Directories whose project
+// is gone are forgotten; ones only missing from this scan keep their history.
+pub fn record_history(history: &mut History, sizes: impl IntoIterator<Item = (PathBuf, u64)>) {
+    let now = unix_now();
+    for (path, bytes) in sizes {
+        history.entry(path).or_default().push((now, bytes));
+    }
+    history.retain(|path, samples| {
+        let excess = samples.len().saturating_sub(HISTORY_SAMPLES);
+        samples.drain(..excess);
+        path.parent().is_some_and(Path::exists)
+    });
+}
+
+pub fn save_history(history: &History) -> io::Result<()> {
+    let dir = cache_dir();
+    ensure_dir(&dir)?;
+    let mut contents = String::new();
+    for (path, samples) in history {
+        for (at, bytes) in samples {
+            contents.push_str(&format!("{at}\t{bytes}\t{}\n", path.to_string_lossy()));
+        }
+    }
+    fs::write(dir.join(HISTORY_FILE), contents)?;
+    // Drop the copy an older version left in the state directory, unless an XDG
+    // override makes it the same file.
+    let old = state_dir().join(HISTORY_FILE);
+    if old != dir.join(HISTORY_FILE) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+pub fn read_paths(file: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(file)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn write_paths(file: &Path, paths: &[PathBuf]) -> io::Result<()> {
+    if let Some(dir) = file.parent() {
+        ensure_dir(dir)?;
+    }
+    let mut contents = String::new();
+    for path in paths {
+        contents.push_str(&path.to_string_lossy());
+        contents.push('\n');
+    }
+    fs::write(file, contents)
+}
@@ -0,0 +1,122 @@
+use bytesize::ByteSize;
+use rayon::prelude::*;
+use std::{path::PathBuf, str::FromStr, time::SystemTime};
+
+use crate::format::format_bytes;
+use crate::git;
+use crate::model::Data;
+
+const DAY: u64 = 86_400;
+
+// What `:suggest` looks for. Size times days untouched has to reach `weight`
+// (in byte-days); a clean git checkout counts double, since it's a clone and an
+// install away. Orphans are suggested whatever their size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SuggestRule {
+    pub weight: f64,
+    pub orphans: bool,
+    pub clean_git: bool,
+}
+
+impl Default for SuggestRule {
+    fn default() -> Self {
+        Self {
+            weight: 1e9 * 30.0,
+            orphans: true,
+            clean_git: true,
+        }
+    }
+}
+
+impl SuggestRule {
+    // e.g. "1GB*30d, orphans, clean_git"; leaving out a word turns that part off.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut rule = Self {
+            weight: f64::INFINITY,
+            orphans: false,
+            clean_git: false,
+        };
+        for term in value.split(',').map(str::trim) {
+            match term {
+                "orphans" => rule.orphans = true,
+                "clean_git" => rule.clean_git = true,
+                _ => {
+                    let (size, days) = term.split_once('*')?;
+                    let size = ByteSize::from_str(size.trim()).ok()?.as_u64();
+                    let days: u64 = days.trim().trim_end_matches('d').parse().ok()?;
+                    rule.weight = size as f64 * days as f64;
+                }
+            }
+        }
+        Some(rule)
+    }
+}
+
+// The rows worth a look and why, for the user to review before deleting.
+pub fn suggest(items: &[Data], rule: &SuggestRule, now: SystemTime) -> Vec<(PathBuf, String)> {
+    items
+        .par_iter()
+        .filter_map(|data| {
+            let days = data
+                .modified
+                .and_then(|modified| now.duration_since(modified).ok())
+                .map_or(0, |age| age.as_secs() / DAY);
+            let weight = data.bytes as f64 * days as f64;
+            let orphan = rule.orphans && data.orphan;
+            // git only runs when doubling could make the difference.
+            let clean = rule.clean_git
+                && weight < rule.weight
+                && weight * 2.0 >= rule.weight
+                && git::is_clean(&data.project);
+            if !orphan && !clean && weight < rule.weight {
+                return None;
+            }
+            let mut reasons = Vec::new();
+            if weight * 2.0 >= rule.weight {
+                reasons.push(format!(
+                    "{} untouched for {days} days",
+                    format_bytes(data.bytes)
+                ));
+            }
+            if orphan {
+                reasons.push("no package.json or lockfile".to_string());
+            }
+            if clean {
+                reasons.push("clean git checkout".to_string());
+            }
+            Some((data.path.clone(), reasons.join(", ")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SuggestRule, suggest};
+    use crate::model::Data;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn suggests_rows_heavy_enough_for_their_age() {
+        let rule = SuggestRule::parse("1GB*30d").unwrap();
+        assert!(!rule.orphans && !rule.clean_git);
+        assert_eq!(None, SuggestRule::parse("1GB*soon"));
+
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000 * 86_400);
+        let row = |name: &str, bytes: u64, days: u64| Data {
+            path: PathBuf::from(name),
+            bytes,
+            modified: Some(now - Duration::from_secs(days * 86_400)),
+            ..Default::default()
+        };
+        let items = [
+            row("big-and-old", 2_000_000_000, 60),
+            row("big-but-fresh", 2_000_000_000, 3),
+            row("small-and-ancient", 1_000_000, 900),
+        ];
+        let suggested = suggest(&items, &rule, now);
+        assert_eq!(1, suggested.len());
+        assert_eq!(PathBuf::from("big-and-old"), suggested[0].0);
+        assert!(suggested[0].1.contains("untouched for 60 days"));
+    }
+}
@@ -0,0 +1,45 @@
+use color_eyre::{Result, config::HookBuilder, eyre};
+use crossterm::{
+    execute,
+    terminal::{EnterAlternateScreen, enable_raw_mode},
+};
+use ratatui::{DefaultTerminal, Terminal, backend::CrosstermBackend};
+use std::{
+    io::{self, stdout},
+    panic,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+// Only set while the TUI owns the terminal, so `list` and friends never print the
+// escape sequences that leave the alternate screen.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// Panic and error reports first put the terminal back into cooked mode on the main
+// screen; otherwise they land in the alternate screen and the shell is left in raw
+// mode.
+pub fn install_hooks() -> Result<()> {
+    let (panic_hook, eyre_hook) = HookBuilder::default().into_hooks();
+    let eyre_hook = eyre_hook.into_eyre_hook();
+    eyre::set_hook(Box::new(move |error| {
+        restore();
+        eyre_hook(error)
+    }))?;
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        eprintln!("{}", panic_hook.panic_report(info));
+    }));
+    Ok(())
+}
+
+pub fn init() -> io::Result<DefaultTerminal> {
+    enable_raw_mode()?;
+    ACTIVE.store(true, Ordering::SeqCst);
+    execute!(stdout(), EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(stdout()))
+}
+
+pub fn restore() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        ratatui::restore();
+    }
+}
@@ -0,0 +1,203 @@
+use color_eyre::{Result, eyre::eyre};
+use sha2::{Digest, Sha256};
+use std::{
+    env::{self, consts},
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::json::{self, Value};
+
+const RELEASES: &str =
+    "https://api.github.com/repos/Juan-LukeKlopper/node_module_cleaner/releases/latest";
+const BINARY: &str = "node-module-finder-rs";
+
+// Replaces the running executable with the latest GitHub release built for this
+// platform, but only once the download matches its published SHA-256. The hash
+// comes from the same release, so it catches a corrupted download, not a
+// tampered release. The assets are built by .github/workflows/release.yml.
+pub fn run() -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let release = fetch(RELEASES)?;
+    let release = json::parse(&String::from_utf8_lossy(&release))
+        .ok_or_else(|| eyre!("GitHub returned a release that isn't JSON"))?;
+    let tag = release
+        .get("tag_name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| eyre!("the latest release has no tag"))?;
+    if !newer(tag, current) {
+        println!("Already up to date ({current})");
+        return Ok(());
+    }
+
+    let assets = assets(&release);
+    let name = asset_name();
+    let (_, url) = assets
+        .iter()
+        .find(|(asset, _)| *asset == name)
+        .ok_or_else(|| eyre!("release {tag} has no {name} binary for this platform"))?;
+    let sidecar = format!("{name}.sha256");
+    let expected = assets
+        .iter()
+        .find_map(|(asset, url)| match *asset {
+            asset if asset == sidecar => Some((*url, None)),
+            "SHA256SUMS" => Some((*url, Some(name.as_str()))),
+            _ => None,
+        })
+        .map(|(url, file)| {
+            fetch(url).map(|sums| checksum_for(&String::from_utf8_lossy(&sums), file))
+        })
+        .transpose()?
+        .flatten()
+        .ok_or_else(|| eyre!("release {tag} publishes no SHA-256 for {name}, not installing it"))?;
+
+    println!("Downloading {name} {tag}...");
+    let binary = fetch(url)?;
+    let actual = hex(&Sha256::digest(&binary));
+    if actual != expected {
+        return Err(eyre!(
+            "checksum mismatch for {name}: expected {expected}, got {actual}"
+        ));
+    }
+    let exe = env::current_exe()?.canonicalize()?;
+    replace(&exe, &binary)?;
+    println!("Updated {} from {current} to {tag}", exe.display());
+    Ok(())
+}
+
+// Through curl, like webhooks, so proxies and certificates behave as they do for
+// everything else on the machine.
+fn fetch(url: &str) -> Result<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "-H", "User-Agent: node-module-finder-rs", url])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|error| eyre!("could not run curl: {error}"))?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "could not download {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+// e.g. "node-module-finder-rs-x86_64-linux", with ".exe" on Windows.
+fn asset_name() -> String {
+    format!(
+        "{BINARY}-{}-{}{}",
+        consts::ARCH,
+        consts::OS,
+        consts::EXE_SUFFIX
+    )
+}
+
+fn assets(release: &Value) -> Vec<(&str, &str)> {
+    let Some(Value::Array(assets)) = release.get("assets") else {
+        return Vec::new();
+    };
+    assets
+        .iter()
+        .filter_map(|asset| {
+            let name = asset.get("name")?.as_str()?;
+            let url = asset.get("browser_download_url")?.as_str()?;
+            Some((name, url))
+        })
+        .collect()
+}
+
+// A `.sha256` file holds just the hash (sometimes followed by the file name);
+// SHA256SUMS has a "hash  name" line per asset.
+fn checksum_for(contents: &str, name: Option<&str>) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let hash = fields.next()?;
+        let file = fields.next().map(|file| file.trim_start_matches('*'));
+        let wanted = name.is_none_or(|name| file == Some(name));
+        (wanted && hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| hash.to_ascii_lowercase())
+    })
+}
+
+// "v0.2.0" against "0.1.2", compared number by number.
+fn newer(tag: &str, current: &str) -> bool {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .trim_start_matches('v')
+            .split(['.', '-'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    parts(tag) > parts(current)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+// Written next to the executable first, so the swap is a rename on the same
+// filesystem. Windows won't overwrite a running executable but lets it be moved.
+fn replace(exe: &Path, binary: &[u8]) -> Result<()> {
+    let staged = sibling(exe, "new");
+    fs::write(&staged, binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+    let old = sibling(exe, "old");
+    if cfg!(windows) {
+        let _ = fs::remove_file(&old);
+        fs::rename(exe, &old)?;
+    }
+    fs::rename(&staged, exe).map_err(|error| {
+        let _ = fs::remove_file(&staged);
+        // Put the running executable back rather than leave nothing at its path.
+        if cfg!(windows) {
+            let _ = fs::rename(&old, exe);
+        }
+        eyre!("could not replace {}: {error}", exe.display())
+    })
+}
+
+fn sibling(exe: &Path, extension: &str) -> PathBuf {
+    let mut name = exe.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{extension}"));
+    exe.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{asset_name, assets, checksum_for, newer};
+    use crate::json;
+
+    #[test]
+    fn finds_the_platform_binary_and_its_checksum() {
+        let release = json::parse(&format!(
+            r#"{{"tag_name": "v9.0.0", "assets": [
+                {{"name": "{}", "browser_download_url": "https://example.com/bin"}},
+                {{"name": "SHA256SUMS", "browser_download_url": "https://example.com/sums"}}
+            ]}}"#,
+            asset_name()
+        ))
+        .unwrap();
+        assert_eq!(
+            vec![
+                (asset_name().as_str(), "https://example.com/bin"),
+                ("SHA256SUMS", "https://example.com/sums")
+            ],
+            assets(&release)
+        );
+
+        let hash = "ab".repeat(32);
+        let sums = format!("{}  other\n{}  {}\n", "cd".repeat(32), hash, asset_name());
+        assert_eq!(Some(hash.clone()), checksum_for(&sums, Some(&asset_name())));
+        assert_eq!(Some(hash.clone()), checksum_for(&format!("{hash}\n"), None));
+        assert_eq!(None, checksum_for("not a hash", None));
+
+        assert!(newer("v0.2.0", "0.1.9"));
+        assert!(newer("0.1.10", "0.1.9"));
+        assert!(!newer("v0.1.2", "0.1.2"));
+    }
+}
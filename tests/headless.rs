@@ -0,0 +1,52 @@
+use node_module_finder_rs::app::App;
+use node_module_finder_rs::config::Config;
+use node_module_finder_rs::model::Data;
+use ratatui::{
+    Terminal,
+    backend::TestBackend,
+    crossterm::event::{Event, KeyCode, KeyEvent},
+};
+use std::path::PathBuf;
+
+fn key(code: KeyCode) -> Event {
+    Event::Key(KeyEvent::from(code))
+}
+
+fn text(terminal: &Terminal<TestBackend>) -> String {
+    let buffer = terminal.backend().buffer();
+    buffer
+        .content()
+        .chunks(buffer.area.width as usize)
+        .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn scripted_keys_drive_the_app_through_the_library() {
+    let items = ["web", "api"]
+        .into_iter()
+        .zip([300_000_000, 20_000_000])
+        .map(|(name, bytes)| Data {
+            path: PathBuf::from(format!("/work/{name}/node_modules")),
+            project: PathBuf::from(format!("/work/{name}")),
+            bytes,
+            ..Default::default()
+        })
+        .collect();
+    let config = Config {
+        root: Some(PathBuf::from("/work")),
+        ..Config::default()
+    };
+    let mut app = App::with_items(config, items, Default::default());
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+
+    // Select the second row and quit; the space after `q` is never handled.
+    let events = ['j', ' ', 'q', ' '].map(|c| key(KeyCode::Char(c)));
+    app.run_with_backend(&mut terminal, events).unwrap();
+
+    let screen = text(&terminal);
+    assert!(screen.contains("☐        /work/api/node_modules"));
+    assert!(screen.contains("☑        /work/web/node_modules"));
+    assert!(screen.contains("1 selected"));
+}